@@ -60,6 +60,7 @@ struct Resources {
     mesh: SharedMesh,
     mesh_width: f32,
     mesh_height: f32,
+    mesh_bottom: f32,
     vertex_buffer: wgpu::Buffer,
     index_buffer: wgpu::Buffer,
     texture: SharedTexture,
@@ -151,7 +152,14 @@ impl Resources {
         let view = texture
             .create_texture(&device, &queue)
             .create_view(&Default::default());
-        let sampler = device.create_sampler(&Default::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("basil-sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
 
         let texture_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("basil-texture-group"),
@@ -197,6 +205,7 @@ impl Resources {
             mesh: mesh.clone(),
             mesh_width: mesh.width(),
             mesh_height: mesh.height(),
+            mesh_bottom: mesh.bottom(),
             vertex_buffer,
             index_buffer,
             depth_width: 1,
@@ -239,6 +248,16 @@ impl Resources {
                             offset: 24,
                             shader_location: 2,
                         },
+                        wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32x4,
+                            offset: 32,
+                            shader_location: 3,
+                        },
+                        wgpu::VertexAttribute {
+                            format: wgpu::VertexFormat::Float32x3,
+                            offset: 48,
+                            shader_location: 4,
+                        },
                     ],
                 }],
             },
@@ -325,6 +344,7 @@ impl Component for MeshViewer {
 
                 resources.mesh_width = ctx.props().mesh.width();
                 resources.mesh_height = ctx.props().mesh.height();
+                resources.mesh_bottom = ctx.props().mesh.bottom();
 
                 resources.vertex_buffer = vertex;
                 resources.index_buffer = index;
@@ -430,11 +450,13 @@ impl Component for MeshViewer {
         let aspect = width as f32 / height as f32;
 
         let (sin, cos) = self.angle.sin_cos();
-        let mesh_height = resources.mesh_height / 2.0;
+        let mesh_span = (resources.mesh_height - resources.mesh_bottom) / 2.0;
+        let mesh_height = resources.mesh_bottom + mesh_span;
+        let mesh_target = resources.mesh_bottom + mesh_span / 2.0;
         let mesh_radius = (resources.mesh_width * 0.75).max(0.1);
         let position = Vec3::new(cos * mesh_radius, mesh_height, sin * mesh_radius);
         let world = Mat4::from_translation(position);
-        let view = Mat4::look_at_rh(position, Vec3::new(0.0, mesh_height / 2.0, 0.0), Vec3::Y);
+        let view = Mat4::look_at_rh(position, Vec3::new(0.0, mesh_target, 0.0), Vec3::Y);
         let proj = Mat4::perspective_infinite_rh(std::f32::consts::PI / 2.0, aspect, 0.1);
         let view_proj = proj * view * world.inverse();
 
@@ -2,8 +2,10 @@ pub mod dna_options;
 pub mod mesh_viewer;
 pub mod plant_viewer;
 pub mod slider;
+pub mod texture_upload;
 
 pub use dna_options::DnaOptions;
 pub use mesh_viewer::MeshViewer;
 pub use plant_viewer::PlantViewer;
 pub use slider::Slider;
+pub use texture_upload::TextureUpload;
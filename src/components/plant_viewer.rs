@@ -3,22 +3,34 @@ use yew::prelude::*;
 
 use super::MeshViewer;
 use crate::{
-    generate::PlantDna,
-    mesh::{Mesh, SharedMesh},
-    texture::{SharedTexture, Texture},
+    generate::{GenerateContext, Materials, PlantDna, Tessellation},
+    mesh::{Mesh, MeshStats, SharedMesh},
+    texture::SharedTexture,
 };
 
 #[derive(PartialEq, Properties)]
 pub struct Properties {
     pub dna: PlantDna,
+    #[prop_or_default]
+    pub tessellation: Tessellation,
+    #[prop_or(true)]
+    pub roots: bool,
     pub rotation: Option<f32>,
     #[prop_or_default]
     pub on_rotate: Callback<f32>,
+    #[prop_or_default]
+    pub show_stats: bool,
+    #[prop_or_default]
+    pub obstacle: Option<SharedMesh>,
 }
 
 pub struct PlantViewer {
     pub dna: PlantDna,
+    pub tessellation: Tessellation,
+    pub roots: bool,
+    pub obstacle: Option<SharedMesh>,
     pub mesh: SharedMesh,
+    pub stats: MeshStats,
     pub texture: SharedTexture,
 }
 
@@ -27,26 +39,41 @@ impl Component for PlantViewer {
     type Properties = Properties;
 
     fn create(ctx: &Context<Self>) -> Self {
+        let materials = Materials::new(&ctx.props().dna);
+
         let mut mesh = Mesh::default();
         ctx.props().dna.generate_mesh(
             &mut mesh,
             Vec3::ZERO,
             Vec3::new(0.0, 1.0, -0.01).normalize(),
             Vec3::Y,
+            GenerateContext {
+                tessellation: ctx.props().tessellation.settings(),
+                roots: ctx.props().roots,
+                obstacle: ctx.props().obstacle.as_deref(),
+                materials: Some(&materials),
+                ..GenerateContext::new()
+            },
         );
         mesh.calculate_normals();
 
-        let texture = Texture::white();
-
         Self {
             dna: ctx.props().dna.clone(),
+            tessellation: ctx.props().tessellation,
+            roots: ctx.props().roots,
+            obstacle: ctx.props().obstacle.clone(),
+            stats: mesh.stats(),
             mesh: SharedMesh::new(mesh),
-            texture: SharedTexture::new(texture),
+            texture: SharedTexture::new(materials.atlas.texture),
         }
     }
 
     fn changed(&mut self, ctx: &Context<Self>) -> bool {
-        if self.dna != ctx.props().dna {
+        if self.dna != ctx.props().dna
+            || self.tessellation != ctx.props().tessellation
+            || self.roots != ctx.props().roots
+            || self.obstacle != ctx.props().obstacle
+        {
             *self = Self::create(ctx);
         }
 
@@ -54,13 +81,28 @@ impl Component for PlantViewer {
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        let stats = if ctx.props().show_stats {
+            html! {
+                <div class="stats">
+                    { format!("{} vertices, {} triangles, {} organs", self.stats.vertices, self.stats.triangles, self.stats.nodes) }
+                    <br />
+                    { format!("Leaf area {:.2}, bark area {:.2}", self.stats.leaf_area, self.stats.bark_area) }
+                </div>
+            }
+        } else {
+            html!()
+        };
+
         html! {
-            <MeshViewer
-                mesh={ self.mesh.clone() }
-                texture={ self.texture.clone() }
-                rotation={ ctx.props().rotation }
-                on_rotate={ ctx.props().on_rotate.clone() }
-            />
+            <>
+                <MeshViewer
+                    mesh={ self.mesh.clone() }
+                    texture={ self.texture.clone() }
+                    rotation={ ctx.props().rotation }
+                    on_rotate={ ctx.props().on_rotate.clone() }
+                />
+                { stats }
+            </>
         }
     }
 }
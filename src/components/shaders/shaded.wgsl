@@ -5,6 +5,10 @@ struct VertexInput {
 	normal: vec3<f32>; 
 	[[location(2)]]
 	uv: vec2<f32>;
+	[[location(3)]]
+	region: vec4<f32>;
+	[[location(4)]]
+	color: vec3<f32>;
 };
 
 struct VertexOutput {
@@ -14,6 +18,10 @@ struct VertexOutput {
 	w_normal: vec3<f32>;
 	[[location(1)]]
 	uv: vec2<f32>;
+	[[location(2)]]
+	region: vec4<f32>;
+	[[location(3)]]
+	color: vec3<f32>;
 };
 
 struct Uniforms {
@@ -35,6 +43,8 @@ fn vert(in: VertexInput) -> VertexOutput {
 	out.w_normal = normal.xyz;
 
     out.uv = in.uv;
+    out.region = in.region;
+    out.color = in.color;
 
     return out;
 }
@@ -47,7 +57,8 @@ var base_color_sampler: sampler;
 
 [[stage(fragment)]]
 fn frag(in: VertexOutput, [[builtin(front_facing)]] front_facing: bool) -> [[location(0)]] vec4<f32> {
-    let base_color = textureSample(base_color_texture, base_color_sampler, in.uv).rgb;
+    let uv = in.region.xy + fract(in.uv) * (in.region.zw - in.region.xy);
+    let base_color = textureSample(base_color_texture, base_color_sampler, uv).rgb * in.color;
 
 	var normal = in.w_normal;
 	
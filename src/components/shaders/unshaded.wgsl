@@ -5,6 +5,10 @@ struct VertexInput {
 	normal: vec3<f32>; 
 	[[location(2)]]
 	uv: vec2<f32>;
+	[[location(3)]]
+	region: vec4<f32>;
+	[[location(4)]]
+	color: vec3<f32>;
 };
 
 struct VertexOutput {
@@ -12,6 +16,10 @@ struct VertexOutput {
 	position: vec4<f32>;
 	[[location(0)]]
 	uv: vec2<f32>;
+	[[location(1)]]
+	region: vec4<f32>;
+	[[location(2)]]
+	color: vec3<f32>;
 };
 
 struct Uniforms {
@@ -30,6 +38,8 @@ fn vert(in: VertexInput) -> VertexOutput {
     out.position = position;
 
     out.uv = in.uv;
+    out.region = in.region;
+    out.color = in.color;
 
     return out;
 }
@@ -42,7 +52,8 @@ var base_color_sampler: sampler;
 
 [[stage(fragment)]]
 fn frag(in: VertexOutput) -> [[location(0)]] vec4<f32> {
-    let color = textureSample(base_color_texture, base_color_sampler, in.uv);
+    let uv = in.region.xy + fract(in.uv) * (in.region.zw - in.region.xy);
+    let color = textureSample(base_color_texture, base_color_sampler, uv);
 
-    return vec4<f32>(color.xyz, 1.0);
+    return vec4<f32>(color.xyz * in.color, 1.0);
 }
\ No newline at end of file
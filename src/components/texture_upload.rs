@@ -0,0 +1,84 @@
+use gloo_file::{callbacks::FileReader, FileReadError};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::texture::{SharedTexture, Texture};
+
+pub enum Message {
+    Choose(Option<web_sys::File>),
+    Loaded(Result<Vec<u8>, FileReadError>),
+}
+
+#[derive(PartialEq, Properties)]
+pub struct Properties {
+    #[prop_or_default]
+    pub onupload: Callback<SharedTexture>,
+}
+
+pub struct TextureUpload {
+    reader: Option<FileReader>,
+    error: bool,
+}
+
+impl Component for TextureUpload {
+    type Message = Message;
+    type Properties = Properties;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            reader: None,
+            error: false,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Message::Choose(Some(file)) => {
+                let link = ctx.link().clone();
+                let file = gloo_file::File::from(file);
+
+                self.reader = Some(gloo_file::callbacks::read_as_bytes(&file, move |result| {
+                    link.send_message(Message::Loaded(result));
+                }));
+            }
+            Message::Choose(None) => {}
+            Message::Loaded(result) => {
+                self.reader = None;
+
+                let texture = result
+                    .ok()
+                    .and_then(|bytes| Texture::decode_png(&bytes).ok());
+
+                self.error = texture.is_none();
+
+                if let Some(texture) = texture {
+                    ctx.props().onupload.emit(SharedTexture::new(texture));
+                }
+            }
+        }
+
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let onchange = ctx.link().callback(|event: Event| {
+            let input = event.target_unchecked_into::<HtmlInputElement>();
+            Message::Choose(input.files().and_then(|files| files.get(0)))
+        });
+
+        let class = if self.error {
+            "file-input invalid"
+        } else {
+            "file-input"
+        };
+
+        html! {
+            <input
+                { class }
+                type="file"
+                accept="image/png"
+                { onchange }
+            />
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use glam::Vec3;
+use rand::{prelude::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::mesh::{Mesh, Vertex};
+
+use super::{curve::CurvePoint, frame, profile::Profile, Color, Gene, GenerateContext};
+
+const MAX_APPENDAGES: usize = 2048;
+
+const CONE_SIDES: u32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AppendageKind {
+    None,
+    Thorn,
+    Spike,
+    Hair,
+}
+
+impl AppendageKind {
+    pub const ALL: [Self; 4] = [Self::None, Self::Thorn, Self::Spike, Self::Hair];
+
+    pub fn new(rng: &mut impl Rng) -> Self {
+        if rng.gen_range(0.0..1.0) < 0.7 {
+            Self::None
+        } else {
+            Self::ALL[rng.gen_range(1..Self::ALL.len())]
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Thorn => "Thorn",
+            Self::Spike => "Spike",
+            Self::Hair => "Hair",
+        }
+    }
+
+    fn width(&self) -> f32 {
+        match self {
+            Self::None => 0.0,
+            Self::Thorn => 0.4,
+            Self::Spike => 0.12,
+            Self::Hair => 0.05,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Appendages {
+    pub kind: AppendageKind,
+    pub density: Gene,
+    pub length: Gene,
+    pub angle: Gene,
+    pub seed: u64,
+    pub color: Color,
+}
+
+impl Appendages {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            kind: AppendageKind::new(rng),
+            density: rng.gen_range(1.0..50.0).into(),
+            length: rng.gen_range(0.01..0.1).into(),
+            angle: rng.gen_range(0.0..1.0).into(),
+            seed: rng.gen(),
+            color: Color::near(rng, Color::rgb(0.9, 0.85, 0.7), 0.2),
+        }
+    }
+
+    pub fn none() -> Self {
+        Self {
+            kind: AppendageKind::None,
+            density: 0.0.into(),
+            length: 0.0.into(),
+            angle: 0.0.into(),
+            seed: 0,
+            color: Color::rgb(1.0, 1.0, 1.0),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.density.mutate(rng, 10.0, variance);
+        self.length.mutate(rng, 0.02, variance);
+        self.angle.mutate(rng, 0.25, variance);
+        self.color.mutate(rng, variance);
+
+        if rng.gen_range(0.0..1.0) < 0.05 * variance {
+            self.kind = AppendageKind::new(rng);
+        }
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            kind: if rng.gen() { self.kind } else { other.kind },
+            density: self.density.crossover(&other.density, rng),
+            length: self.length.crossover(&other.length, rng),
+            angle: self.angle.crossover(&other.angle, rng),
+            seed: if rng.gen() { self.seed } else { other.seed },
+            color: self.color.crossover(&other.color, rng),
+        }
+    }
+
+    pub fn view(&self, callback: &Callback<AppendagesMessage>) -> Html {
+        let kinds = AppendageKind::ALL.into_iter().map(|kind| {
+            let onclick = callback.reform(move |_| AppendagesMessage::SetKind(kind));
+
+            html! {
+                <option { onclick } selected={ kind == self.kind }>{ kind.name() }</option>
+            }
+        });
+
+        let genes = match self.kind {
+            AppendageKind::None => html!(),
+            _ => html! {
+                <>
+                    { self.density.view("Density", 0.0, 200.0, callback.reform(AppendagesMessage::SetDensity)) }
+                    { self.length.view("Length", 0.0, 0.3, callback.reform(AppendagesMessage::SetLength)) }
+                    { self.angle.view("Angle", -FRAC_PI_2, FRAC_PI_2, callback.reform(AppendagesMessage::SetAngle)) }
+                    { self.color.view("Appendage Color", callback.reform(AppendagesMessage::SetColor)) }
+                </>
+            },
+        };
+
+        html! {
+            <>
+                <div class="property">
+                    { "Appendages" }
+                    <select>{ for kinds }</select>
+                </div>
+                { genes }
+            </>
+        }
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        points: &[CurvePoint],
+        profile: &Profile,
+        radii: &[f32],
+        ctx: &GenerateContext,
+    ) {
+        if self.kind == AppendageKind::None {
+            return;
+        }
+
+        let density = self.density.eval(ctx).max(0.0) / (ctx.scale * ctx.scale).max(f32::EPSILON);
+        let length = self.length.eval(ctx) * ctx.scale;
+        let angle = self.angle.eval(ctx);
+
+        if density <= 0.0 || length <= 0.0 {
+            return;
+        }
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut amount = 0;
+        let mut carry = 0.0;
+
+        for (pair, radii) in points.windows(2).zip(radii.windows(2)) {
+            let (a, b) = (&pair[0], &pair[1]);
+
+            let (ra, rb) = (radii[0], radii[1]);
+            let area = (ra + rb) * 0.5 * TAU * (b.distance - a.distance);
+
+            carry += area * density;
+            let count = carry.floor();
+            carry -= count;
+
+            for _ in 0..count as usize {
+                if amount >= MAX_APPENDAGES {
+                    return;
+                }
+
+                amount += 1;
+
+                let x = rng.gen_range(0.0..1.0);
+                let u = rng.gen_range(0.0..1.0f32);
+
+                let direction = a.direction.lerp(b.direction, x).normalize_or_zero();
+                let up = frame::orthogonalize(direction, a.up.lerp(b.up, x));
+                let right = direction.cross(up);
+
+                let offset = profile.offset(u * TAU);
+                let surface = right * offset.x + up * offset.y;
+                let normal = surface.normalize_or_zero();
+
+                let r = ra + (rb - ra) * x;
+                let base = a.position.lerp(b.position, x) + surface * r;
+
+                let (a_sin, a_cos) = angle.sin_cos();
+                let heading = normal * a_cos + direction * a_sin;
+
+                let length = length * rng.gen_range(0.75..1.0);
+
+                match self.kind {
+                    AppendageKind::None => {}
+                    AppendageKind::Thorn => {
+                        let tip = heading * length + direction * length * 0.3;
+                        self.cone(mesh, base, heading, tip, length);
+                    }
+                    AppendageKind::Spike => {
+                        self.cone(mesh, base, heading, heading * length, length)
+                    }
+                    AppendageKind::Hair => self.card(mesh, base, heading, direction, length),
+                }
+            }
+        }
+    }
+
+    fn cone(&self, mesh: &mut Mesh, base: Vec3, heading: Vec3, tip: Vec3, length: f32) {
+        let width = length * self.kind.width();
+        let up = frame::orthogonalize(heading, heading.any_orthonormal_vector());
+        let right = heading.cross(up);
+
+        let base = base - heading * width * 0.25;
+        let color = self.color.to_array();
+        let first = mesh.vertices.len() as u32;
+
+        for i in 0..CONE_SIDES {
+            let u = i as f32 / CONE_SIDES as f32;
+            let (sin, cos) = (u * TAU).sin_cos();
+
+            mesh.vertices.push(Vertex {
+                position: (base + (right * cos + up * sin) * width * 0.5).into(),
+                normal: [0.0; 3],
+                uv: [u, 0.0],
+                color,
+                ..Default::default()
+            });
+        }
+
+        mesh.vertices.push(Vertex {
+            position: (base + tip).into(),
+            normal: [0.0; 3],
+            uv: [0.5, 1.0],
+            color,
+            ..Default::default()
+        });
+
+        let apex = first + CONE_SIDES;
+
+        for i in 0..CONE_SIDES {
+            mesh.indices.push(first + i);
+            mesh.indices.push(first + (i + 1) % CONE_SIDES);
+            mesh.indices.push(apex);
+        }
+    }
+
+    fn card(&self, mesh: &mut Mesh, base: Vec3, heading: Vec3, direction: Vec3, length: f32) {
+        let width = length * self.kind.width();
+        let side = heading.cross(direction).normalize_or_zero() * width * 0.5;
+        let tip = base + heading * length;
+
+        let color = self.color.to_array();
+        let first = mesh.vertices.len() as u32;
+
+        for (position, uv) in [
+            (base + side, [1.0, 0.0]),
+            (base - side, [0.0, 0.0]),
+            (tip + side, [1.0, 1.0]),
+            (tip - side, [0.0, 1.0]),
+        ] {
+            mesh.vertices.push(Vertex {
+                position: position.into(),
+                normal: [0.0; 3],
+                uv,
+                color,
+                ..Default::default()
+            });
+        }
+
+        mesh.indices.push(first);
+        mesh.indices.push(first + 2);
+        mesh.indices.push(first + 1);
+
+        mesh.indices.push(first + 1);
+        mesh.indices.push(first + 2);
+        mesh.indices.push(first + 3);
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+pub enum AppendagesMessage {
+    SetKind(AppendageKind),
+    SetDensity(Gene),
+    SetLength(Gene),
+    SetAngle(Gene),
+    SetColor(Color),
+}
+
+impl AppendagesMessage {
+    pub fn handle(self, appendages: &mut Appendages) {
+        match self {
+            Self::SetKind(x) => appendages.kind = x,
+            Self::SetDensity(x) => appendages.density = x,
+            Self::SetLength(x) => appendages.length = x,
+            Self::SetAngle(x) => appendages.angle = x,
+            Self::SetColor(x) => appendages.color = x,
+        }
+    }
+}
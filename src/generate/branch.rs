@@ -1,152 +1,450 @@
-use std::f32::consts::{PI, TAU};
+use std::{
+    cell::Cell,
+    f32::consts::{PI, TAU},
+};
 
 use glam::{Quat, Vec3};
-use rand::Rng;
+use rand::{prelude::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use yew::prelude::*;
 
-use crate::{
-    components::Slider,
-    mesh::{Mesh, Vertex},
+use crate::mesh::{Mesh, Organ};
+
+use super::{
+    appendage::{Appendages, AppendagesMessage},
+    curve::{Curve, CurvePoint},
+    frame,
+    profile::Profile,
+    tube::{self, Cap, Joint, Ring},
+    BarkPattern, Color, Gene, GenerateContext, Gradient, Material, PlantDna, PlantMessage,
 };
 
-use super::{PlantDna, PlantMessage};
+const CONTROL_POINTS: usize = 8;
+
+const RING_SPACING: f32 = 2.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RadiusMode {
+    Free,
+    Inherit,
+    Capped,
+}
+
+impl RadiusMode {
+    pub const ALL: [Self; 3] = [Self::Free, Self::Inherit, Self::Capped];
+
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self::ALL[rng.gen_range(0..Self::ALL.len())]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Free => "Free",
+            Self::Inherit => "Inherit",
+            Self::Capped => "Capped",
+        }
+    }
+
+    pub fn apply(&self, radius: f32, parent_radius: f32) -> f32 {
+        if parent_radius <= 0.0 {
+            return radius;
+        }
+
+        match self {
+            Self::Free => radius,
+            Self::Inherit => parent_radius,
+            Self::Capped => radius.min(parent_radius),
+        }
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Branch {
-    pub length: f32,
-    pub radius: f32,
-    pub bend: f32,
-    pub taper: f32,
+    pub length: Gene,
+    pub radius: Gene,
+    pub radius_mode: RadiusMode,
+    pub profile: Profile,
+    pub roughness: Gene,
+    pub cap: Cap,
+    pub weld: bool,
+    pub bend: Gene,
+    pub taper: Gene,
+    pub gravitropism: Gene,
+    pub phototropism: Gene,
+    pub twist: Gene,
+    pub wiggle: Gene,
+    pub seed: u64,
+    pub color: Gradient,
+    pub bark: BarkPattern,
+    pub appendages: Appendages,
     pub end: Box<PlantDna>,
 }
 
 impl Branch {
     pub fn new(rng: &mut impl Rng) -> Self {
         Self {
-            length: rng.gen_range(0.1..2.0),
-            radius: rng.gen_range(0.05..0.5),
-            bend: rng.gen_range(0.0..0.75),
-            taper: rng.gen_range(0.0..1.0),
+            length: rng.gen_range(0.1..2.0).into(),
+            radius: rng.gen_range(0.05..0.5).into(),
+            radius_mode: RadiusMode::new(rng),
+            profile: Profile::new(rng),
+            roughness: rng.gen_range(0.0..0.1).into(),
+            cap: Cap::new(rng),
+            weld: rng.gen(),
+            bend: rng.gen_range(0.0..0.75).into(),
+            taper: rng.gen_range(0.0..1.0).into(),
+            gravitropism: rng.gen_range(-0.5..0.5).into(),
+            phototropism: rng.gen_range(0.0..0.5).into(),
+            twist: rng.gen_range(-1.0..1.0).into(),
+            wiggle: rng.gen_range(0.0..0.25).into(),
+            seed: rng.gen(),
+            color: Gradient::near(rng, Color::rgb(1.0, 0.95, 0.9), 0.2),
+            bark: BarkPattern::new(rng),
+            appendages: Appendages::new(rng),
             end: Box::new(PlantDna::new(rng)),
         }
     }
 
     pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
-        self.length += rng.gen_range(-0.5..0.5) * variance;
-        self.radius += rng.gen_range(-0.25..0.25) * variance;
-        self.bend += rng.gen_range(-0.25..0.25) * variance;
-        self.taper += rng.gen_range(-0.5..0.5) * variance;
+        self.length.mutate(rng, 0.5, variance);
+        self.radius.mutate(rng, 0.25, variance);
+        self.bend.mutate(rng, 0.25, variance);
+        self.taper.mutate(rng, 0.5, variance);
+        self.gravitropism.mutate(rng, 0.25, variance);
+        self.phototropism.mutate(rng, 0.25, variance);
+        self.twist.mutate(rng, 0.5, variance);
+        self.wiggle.mutate(rng, 0.1, variance);
+        self.roughness.mutate(rng, 0.05, variance);
+        self.profile.mutate(rng, variance);
+        self.color.mutate(rng, variance);
+        self.bark.mutate(rng, variance);
+        self.appendages.mutate(rng, variance);
+
+        if rng.gen_range(0.0..1.0) < 0.25 * variance {
+            self.radius_mode = RadiusMode::new(rng);
+        }
+
+        if rng.gen_range(0.0..1.0) < 0.1 * variance {
+            self.cap = Cap::new(rng);
+        }
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            length: self.length.crossover(&other.length, rng),
+            radius: self.radius.crossover(&other.radius, rng),
+            radius_mode: if rng.gen() {
+                self.radius_mode
+            } else {
+                other.radius_mode
+            },
+            profile: if rng.gen() {
+                self.profile.clone()
+            } else {
+                other.profile.clone()
+            },
+            roughness: self.roughness.crossover(&other.roughness, rng),
+            cap: if rng.gen() { self.cap } else { other.cap },
+            weld: if rng.gen() { self.weld } else { other.weld },
+            bend: self.bend.crossover(&other.bend, rng),
+            taper: self.taper.crossover(&other.taper, rng),
+            gravitropism: self.gravitropism.crossover(&other.gravitropism, rng),
+            phototropism: self.phototropism.crossover(&other.phototropism, rng),
+            twist: self.twist.crossover(&other.twist, rng),
+            wiggle: self.wiggle.crossover(&other.wiggle, rng),
+            seed: if rng.gen() { self.seed } else { other.seed },
+            color: self.color.crossover(&other.color, rng),
+            bark: if rng.gen() {
+                self.bark.clone()
+            } else {
+                other.bark.clone()
+            },
+            appendages: self.appendages.crossover(&other.appendages, rng),
+            end: Box::new(self.end.crossover(&other.end, rng)),
+        }
     }
 
     pub fn view(&self, callback: &Callback<BranchMessage>) -> Html {
+        let radius_modes = RadiusMode::ALL.into_iter().map(|mode| {
+            let onclick = callback.reform(move |_| BranchMessage::SetRadiusMode(mode));
+
+            html! {
+                <option { onclick } selected={ mode == self.radius_mode }>{ mode.name() }</option>
+            }
+        });
+
+        let caps = Cap::ALL.into_iter().map(|cap| {
+            let onclick = callback.reform(move |_| BranchMessage::SetCap(cap));
+
+            html! {
+                <option { onclick } selected={ cap == self.cap }>{ cap.name() }</option>
+            }
+        });
+
+        let weld = !self.weld;
+        let onclick = callback.reform(move |_| BranchMessage::SetWeld(weld));
+
         html! {
             <>
+                { self.length.view("Length", 0.1, 2.0, callback.reform(BranchMessage::SetLength)) }
+                { self.radius.view("Radius", 0.05, 0.5, callback.reform(BranchMessage::SetRadius)) }
                 <div class="property">
-                    { "Length" }
-                    <Slider
-                        min=0.1
-                        max=2.0
-                        value={ self.length }
-                        oninput={ callback.reform(BranchMessage::SetLength) }
-                    />
-                </div>
-                <div class="property">
-                    { "Radius" }
-                    <Slider
-                        min=0.05
-                        max=0.5
-                        value={ self.radius }
-                        oninput={ callback.reform(BranchMessage::SetRadius) }
-                    />
+                    { "Radius Mode" }
+                    <select>{ for radius_modes }</select>
                 </div>
+                { self.profile.view(&callback.reform(BranchMessage::SetProfile)) }
+                { self.roughness.view("Roughness", 0.0, 0.5, callback.reform(BranchMessage::SetRoughness)) }
                 <div class="property">
-                    { "Bend" }
-                    <Slider
-                        min=0.0
-                        max=0.75
-                        value={ self.bend }
-                        oninput={ callback.reform(BranchMessage::SetBend) }
-                    />
+                    { "End Caps" }
+                    <select>{ for caps }</select>
                 </div>
                 <div class="property">
-                    { "Taper" }
-                    <Slider
-                        min=0.0
-                        max=1.0
-                        value={ self.taper }
-                        oninput={ callback.reform(BranchMessage::SetTaper) }
-                    />
+                    { "Weld To Parent" }
+                    <input type="checkbox" checked={ self.weld } { onclick } />
                 </div>
+                { self.bend.view("Bend", 0.0, 0.75, callback.reform(BranchMessage::SetBend)) }
+                { self.taper.view("Taper", 0.0, 1.0, callback.reform(BranchMessage::SetTaper)) }
+                { self.gravitropism.view("Gravitropism", -2.0, 2.0, callback.reform(BranchMessage::SetGravitropism)) }
+                { self.phototropism.view("Phototropism", 0.0, 2.0, callback.reform(BranchMessage::SetPhototropism)) }
+                { self.twist.view("Twist", -PI, PI, callback.reform(BranchMessage::SetTwist)) }
+                { self.wiggle.view("Wiggle", 0.0, 1.0, callback.reform(BranchMessage::SetWiggle)) }
+                { self.color.view(&callback.reform(BranchMessage::SetColor)) }
+                { self.bark.view(&callback.reform(BranchMessage::SetBark)) }
+                { self.appendages.view(&callback.reform(BranchMessage::ChangeAppendages)) }
                 { self.end.view(&callback.reform(|msg| BranchMessage::ChangeEnd(Box::new(msg)))) }
             </>
         }
     }
 
-    pub fn generate(&self, mesh: &mut Mesh, mut start: Vec3, mut direction: Vec3, real_up: Vec3) {
-        let steps = 5;
-        let radial = 5;
+    fn curve(
+        &self,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        length: f32,
+        ctx: &GenerateContext,
+    ) -> Curve {
+        let bend = self.bend.eval(ctx);
+        let gravitropism = self.gravitropism.eval(ctx);
+        let phototropism = self.phototropism.eval(ctx);
+        let twist = self.twist.eval(ctx);
+        let wiggle = self.wiggle.eval(ctx);
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let dt = 1.0 / CONTROL_POINTS as f32;
+
+        let mut position = start;
+        let mut heading = direction;
+        let mut frame_up = frame::orthogonalize(direction, up);
+        let mut control = vec![position];
+
+        for _ in 0..CONTROL_POINTS {
+            let right = heading.cross(frame_up).normalize_or_zero();
+            let bend = Quat::from_axis_angle(right, -bend * PI * dt);
+            let twist = Quat::from_axis_angle(heading, twist * length * dt);
+
+            let noise = Vec3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+
+            let next = bend * heading
+                + (Vec3::Y * gravitropism + ctx.light * phototropism + noise * wiggle) * dt;
+            let next = next.normalize_or_zero();
+
+            if next != Vec3::ZERO {
+                frame_up = frame::transport(twist * frame_up, heading, next);
+                heading = next;
+            }
+
+            position += heading * length * dt;
+            control.push(position);
+        }
+
+        Curve::new(control, direction, up, twist)
+    }
+
+    fn joint(
+        &self,
+        mesh: &Mesh,
+        point: &CurvePoint,
+        radial: u32,
+        radius: f32,
+        ctx: &GenerateContext,
+    ) -> Option<Ring> {
+        let joint = ctx
+            .joint
+            .filter(|joint| self.weld && !joint.welded.get() && joint.ring.radial == radial)?;
+
+        let region = ctx.materials.map(|materials| {
+            materials
+                .region(&Material::Bark(self.bark.clone()))
+                .to_array()
+        });
+
+        let right = point.right();
+        let tolerance = radius * 1e-3;
+
+        let coincides = (0..=radial).all(|j| {
+            let vertex = &mesh.vertices[(joint.ring.first + j) as usize];
+
+            let u = j as f32 / radial as f32;
+            let offset = self.profile.offset(u * TAU) * radius;
+            let p = point.position + right * offset.x + point.up * offset.y;
+
+            Vec3::from(vertex.position).distance(p) <= tolerance
+                && region.iter().all(|region| vertex.region == *region)
+        });
+
+        joint.welded.set(coincides);
+        coincides.then_some(joint.ring)
+    }
 
-        let right = direction.cross(real_up).normalize();
-        let mut up = right.cross(direction).normalize();
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let length = self.length.eval(&ctx) * ctx.scale;
+        let radius = self.radius.eval(&ctx) * ctx.scale;
+        let radius = self.radius_mode.apply(radius, ctx.parent_radius);
+        let end_radius = radius * self.taper.eval(&ctx);
 
-        let end_radius = self.radius * self.taper;
+        let curve = self.curve(start, direction, up, length, &ctx);
+        let tessellation = ctx.tessellation;
+        let roughness = self.roughness.eval(&ctx).max(0.0);
 
-        let bend = Quat::from_axis_angle(right, -self.bend / steps as f32 * PI);
+        let spacing = if roughness > 0.0 || self.profile != Profile::Circle {
+            radius * RING_SPACING
+        } else {
+            f32::INFINITY
+        };
 
-        for i in 0..=steps {
-            let x = i as f32 / steps as f32;
-            let radius = self.radius * (1.0 - x) + end_radius * x;
+        let points = curve.adaptive_spaced(
+            tessellation.ring_angle(radius),
+            spacing,
+            tessellation.curve_samples,
+        );
 
-            for j in 0..radial {
-                let r = j as f32 / radial as f32 * TAU;
-                let (r_sin, r_cos) = r.sin_cos();
+        let first = mesh.vertices.len();
 
-                let p = start + right * r_cos * radius + up * r_sin * radius;
+        let radial = self.profile.radial(tessellation.radial(radius));
+        let joint = self.joint(mesh, &points[0], radial, radius, &ctx);
 
-                mesh.vertices.push(Vertex {
-                    position: p.into(),
-                    normal: [0.0; 3],
-                    uv: [0.0; 2],
-                });
+        let radius_at = |point: &CurvePoint| radius * (1.0 - point.t) + end_radius * point.t;
 
-                if i > 0 {
-                    let i0 = mesh.vertices.len() as u32 - 1;
-                    let i1 = if j < radial - 1 {
-                        i0 + 1
-                    } else {
-                        i0 - radial + 1
-                    };
+        let last = tube::profiled(mesh, &points, radial, &self.profile, radius_at, joint);
 
-                    let i2 = i0 - radial;
-                    let i3 = i1 - radial;
+        let mut rng = StdRng::seed_from_u64(self.seed);
 
-                    mesh.indices.push(i0);
-                    mesh.indices.push(i2);
-                    mesh.indices.push(i1);
+        let (skip, v) = match joint {
+            Some(joint) => (1, mesh.vertices[joint.first as usize].uv[1]),
+            None => (0, 0.0),
+        };
 
-                    mesh.indices.push(i1);
-                    mesh.indices.push(i2);
-                    mesh.indices.push(i3);
+        let scales = points
+            .iter()
+            .map(|point| {
+                if point.t > 0.0 && point.t < 1.0 {
+                    1.0 + roughness * rng.gen_range(-1.0..1.0)
+                } else {
+                    1.0
                 }
-            }
+            })
+            .collect::<Vec<_>>();
+
+        let rings = mesh.vertices[first..].chunks_mut(radial as usize + 1);
+        let scaled = points.iter().zip(&scales).skip(skip);
+
+        for (ring, (point, &scale)) in rings.zip(scaled) {
+            let color = self.color.at(point.t).to_array();
 
-            if i < steps {
-                start += direction * x * self.length;
-                direction = bend * direction;
-                up = bend * up;
+            for vertex in ring {
+                let offset = Vec3::from(vertex.position) - point.position;
+
+                vertex.position = (point.position + offset * scale).into();
+                vertex.uv[1] += v;
+                vertex.color = color;
             }
         }
 
-        self.end.generate_mesh(mesh, start, direction, real_up);
+        if joint.is_none() {
+            let base = Ring {
+                first: first as u32,
+                radial,
+            };
+
+            self.cap
+                .close(mesh, base, &points[0], &self.profile, radius, false);
+        }
+
+        let radii = points
+            .iter()
+            .zip(&scales)
+            .map(|(point, scale)| radius_at(point) * scale)
+            .collect::<Vec<_>>();
+
+        self.appendages
+            .generate(mesh, &points, &self.profile, &radii, &ctx);
+
+        let vertices = first..mesh.vertices.len();
+        let node = mesh.tag(vertices.clone(), Organ::Branch);
+        ctx.apply_material(mesh, vertices, Material::Bark(self.bark.clone()));
+
+        let end = points.last().unwrap();
+
+        let joint = Joint {
+            ring: last,
+            welded: Cell::new(false),
+        };
+
+        let child = GenerateContext {
+            parent_radius: end_radius,
+            joint: Some(&joint),
+            ..ctx.child()
+        };
+
+        self.end
+            .generate_mesh(mesh, end.position, end.direction, end.up, child);
+
+        if !joint.welded.get() {
+            let first = mesh.vertices.len();
+
+            self.cap
+                .close(mesh, last, end, &self.profile, end_radius, true);
+
+            for vertex in mesh.vertices[first..].iter_mut() {
+                vertex.organ = Organ::Branch.id();
+                vertex.node = node;
+            }
+
+            let vertices = first..mesh.vertices.len();
+            ctx.apply_material(mesh, vertices, Material::Bark(self.bark.clone()));
+        }
     }
 }
 
 pub enum BranchMessage {
-    SetLength(f32),
-    SetRadius(f32),
-    SetBend(f32),
-    SetTaper(f32),
+    SetLength(Gene),
+    SetRadius(Gene),
+    SetRadiusMode(RadiusMode),
+    SetProfile(Profile),
+    SetRoughness(Gene),
+    SetCap(Cap),
+    SetWeld(bool),
+    SetBend(Gene),
+    SetTaper(Gene),
+    SetGravitropism(Gene),
+    SetPhototropism(Gene),
+    SetTwist(Gene),
+    SetWiggle(Gene),
+    SetColor(Gradient),
+    SetBark(BarkPattern),
+    ChangeAppendages(AppendagesMessage),
     ChangeEnd(Box<PlantMessage>),
 }
 
@@ -155,8 +453,20 @@ impl BranchMessage {
         match self {
             Self::SetLength(x) => branch.length = x,
             Self::SetRadius(x) => branch.radius = x,
+            Self::SetRadiusMode(x) => branch.radius_mode = x,
+            Self::SetProfile(x) => branch.profile = x,
+            Self::SetRoughness(x) => branch.roughness = x,
+            Self::SetCap(x) => branch.cap = x,
+            Self::SetWeld(x) => branch.weld = x,
             Self::SetBend(x) => branch.bend = x,
             Self::SetTaper(x) => branch.taper = x,
+            Self::SetGravitropism(x) => branch.gravitropism = x,
+            Self::SetPhototropism(x) => branch.phototropism = x,
+            Self::SetTwist(x) => branch.twist = x,
+            Self::SetWiggle(x) => branch.wiggle = x,
+            Self::SetColor(x) => branch.color = x,
+            Self::SetBark(x) => branch.bark = x,
+            Self::ChangeAppendages(msg) => msg.handle(&mut branch.appendages),
             Self::ChangeEnd(msg) => msg.handle(rng, &mut branch.end),
         }
     }
@@ -0,0 +1,553 @@
+use std::collections::HashMap;
+
+use glam::Vec3;
+use rand::{prelude::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::mesh::{Mesh, Organ};
+
+use super::{
+    curve::{Curve, CurvePoint},
+    frame, tube, BarkPattern, Color, Gene, GenerateContext, Gradient, Material, PlantDna,
+    PlantMessage,
+};
+
+const MAX_ATTRACTORS: usize = 1000;
+
+const MAX_NODES: usize = 2000;
+
+const MAX_ITERATIONS: usize = 200;
+
+const PIPE_EXPONENT: f32 = 2.5;
+
+struct Grid {
+    size: f32,
+    cells: HashMap<[i32; 3], Vec<usize>>,
+}
+
+impl Grid {
+    fn new(size: f32) -> Self {
+        Self {
+            size,
+            cells: HashMap::new(),
+        }
+    }
+
+    fn cell(&self, position: Vec3) -> [i32; 3] {
+        let cell = (position / self.size).floor();
+        [cell.x as i32, cell.y as i32, cell.z as i32]
+    }
+
+    fn insert(&mut self, node: usize, position: Vec3) {
+        let cell = self.cell(position);
+        self.cells.entry(cell).or_default().push(node);
+    }
+
+    fn near(&self, position: Vec3) -> impl Iterator<Item = usize> + '_ {
+        let [x, y, z] = self.cell(position);
+
+        (-1..=1)
+            .flat_map(move |dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| [dx, dy, dz])))
+            .filter_map(move |[dx, dy, dz]| self.cells.get(&[x + dx, y + dy, z + dz]))
+            .flatten()
+            .copied()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Envelope {
+    Sphere,
+    Cone,
+    Ellipsoid,
+}
+
+impl Envelope {
+    pub const ALL: [Self; 3] = [Self::Sphere, Self::Cone, Self::Ellipsoid];
+
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self::ALL[rng.gen_range(0..Self::ALL.len())]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sphere => "Sphere",
+            Self::Cone => "Cone",
+            Self::Ellipsoid => "Ellipsoid",
+        }
+    }
+
+    fn contains(&self, p: Vec3) -> bool {
+        match self {
+            Self::Sphere | Self::Ellipsoid => p.length_squared() <= 1.0,
+            Self::Cone => {
+                let radius = (1.0 - p.y) / 2.0;
+                p.x * p.x + p.z * p.z <= radius * radius
+            }
+        }
+    }
+
+    fn extents(&self, width: f32, height: f32) -> (f32, f32) {
+        match self {
+            Self::Sphere => (width, width),
+            Self::Cone | Self::Ellipsoid => (width, height / 2.0),
+        }
+    }
+}
+
+struct Node {
+    position: Vec3,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    radius: f32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Colonization {
+    pub envelope: Envelope,
+    pub attractors: Gene,
+    pub width: Gene,
+    pub height: Gene,
+    pub trunk: Gene,
+    pub influence: Gene,
+    pub kill: Gene,
+    pub step: Gene,
+    pub radius: Gene,
+    pub seed: u64,
+    pub color: Gradient,
+    pub bark: BarkPattern,
+    pub end: Box<PlantDna>,
+}
+
+impl Colonization {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            envelope: Envelope::new(rng),
+            attractors: rng.gen_range(50.0..400.0).into(),
+            width: rng.gen_range(0.5..1.5).into(),
+            height: rng.gen_range(1.0..3.0).into(),
+            trunk: rng.gen_range(0.2..1.0).into(),
+            influence: rng.gen_range(4.0..10.0).into(),
+            kill: rng.gen_range(1.0..2.0).into(),
+            step: rng.gen_range(0.05..0.15).into(),
+            radius: rng.gen_range(0.005..0.02).into(),
+            seed: rng.gen(),
+            color: Gradient::near(rng, Color::rgb(1.0, 0.95, 0.9), 0.2),
+            bark: BarkPattern::new(rng),
+            end: Box::new(PlantDna::new(rng)),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.attractors.mutate(rng, 50.0, variance);
+        self.width.mutate(rng, 0.25, variance);
+        self.height.mutate(rng, 0.5, variance);
+        self.trunk.mutate(rng, 0.25, variance);
+        self.influence.mutate(rng, 1.0, variance);
+        self.kill.mutate(rng, 0.25, variance);
+        self.step.mutate(rng, 0.02, variance);
+        self.radius.mutate(rng, 0.005, variance);
+        self.color.mutate(rng, variance);
+        self.bark.mutate(rng, variance);
+        self.end.mutate(rng, variance);
+
+        if rng.gen_range(0.0..1.0) < 0.1 * variance {
+            self.envelope = Envelope::new(rng);
+        }
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            envelope: if rng.gen() {
+                self.envelope
+            } else {
+                other.envelope
+            },
+            attractors: self.attractors.crossover(&other.attractors, rng),
+            width: self.width.crossover(&other.width, rng),
+            height: self.height.crossover(&other.height, rng),
+            trunk: self.trunk.crossover(&other.trunk, rng),
+            influence: self.influence.crossover(&other.influence, rng),
+            kill: self.kill.crossover(&other.kill, rng),
+            step: self.step.crossover(&other.step, rng),
+            radius: self.radius.crossover(&other.radius, rng),
+            seed: if rng.gen() { self.seed } else { other.seed },
+            color: self.color.crossover(&other.color, rng),
+            bark: if rng.gen() {
+                self.bark.clone()
+            } else {
+                other.bark.clone()
+            },
+            end: Box::new(self.end.crossover(&other.end, rng)),
+        }
+    }
+
+    pub fn view(&self, callback: &Callback<ColonizationMessage>) -> Html {
+        let envelopes = Envelope::ALL.into_iter().map(|envelope| {
+            let onclick = callback.reform(move |_| ColonizationMessage::SetEnvelope(envelope));
+
+            html! {
+                <option { onclick } selected={ envelope == self.envelope }>{ envelope.name() }</option>
+            }
+        });
+
+        html! {
+            <>
+                <div class="property">
+                    { "Envelope" }
+                    <select>{ for envelopes }</select>
+                </div>
+                { self.attractors.view("Attractors", 1.0, MAX_ATTRACTORS as f32, callback.reform(ColonizationMessage::SetAttractors)) }
+                { self.width.view("Width", 0.1, 3.0, callback.reform(ColonizationMessage::SetWidth)) }
+                { self.height.view("Height", 0.1, 5.0, callback.reform(ColonizationMessage::SetHeight)) }
+                { self.trunk.view("Trunk", 0.0, 3.0, callback.reform(ColonizationMessage::SetTrunk)) }
+                { self.influence.view("Influence", 1.0, 20.0, callback.reform(ColonizationMessage::SetInfluence)) }
+                { self.kill.view("Kill Distance", 0.5, 5.0, callback.reform(ColonizationMessage::SetKill)) }
+                { self.step.view("Step", 0.01, 0.5, callback.reform(ColonizationMessage::SetStep)) }
+                { self.radius.view("Tip Radius", 0.001, 0.05, callback.reform(ColonizationMessage::SetRadius)) }
+                { self.color.view(&callback.reform(ColonizationMessage::SetColor)) }
+                { self.bark.view(&callback.reform(ColonizationMessage::SetBark)) }
+                <div class="container">
+                    { self.end.view(&callback.reform(|msg| ColonizationMessage::ChangeEnd(Box::new(msg)))) }
+                </div>
+            </>
+        }
+    }
+
+    fn attractors(
+        &self,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: &GenerateContext,
+    ) -> Vec<Vec3> {
+        let amount = (self.attractors.eval(ctx).round().max(0.0) as usize).min(MAX_ATTRACTORS);
+        let width = self.width.eval(ctx).max(0.0) * ctx.scale;
+        let height = self.height.eval(ctx).max(0.0) * ctx.scale;
+        let trunk = self.trunk.eval(ctx).max(0.0) * ctx.scale;
+
+        let (across, along) = self.envelope.extents(width, height);
+        let center = start + direction * (trunk + along);
+        let right = direction.cross(up);
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut points = Vec::with_capacity(amount);
+
+        for _ in 0..amount * 8 {
+            if points.len() >= amount {
+                break;
+            }
+
+            let p = Vec3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+
+            if self.envelope.contains(p) {
+                points.push(
+                    center + right * p.x * across + direction * p.y * along + up * p.z * across,
+                );
+            }
+        }
+
+        points
+    }
+
+    fn skeleton(&self, start: Vec3, direction: Vec3, up: Vec3, ctx: &GenerateContext) -> Vec<Node> {
+        let step = self.step.eval(ctx).max(0.001) * ctx.scale;
+        let influence = self.influence.eval(ctx).max(1.0) * step;
+        let kill = self.kill.eval(ctx).clamp(0.5, influence / step) * step;
+
+        let mut attractors = self.attractors(start, direction, up, ctx);
+
+        let mut nodes = vec![Node {
+            position: start,
+            parent: None,
+            children: Vec::new(),
+            radius: 0.0,
+        }];
+
+        let top = attractors
+            .iter()
+            .map(|attractor| (*attractor - start).dot(direction))
+            .fold(0.0, f32::max);
+
+        while nodes.len() < MAX_NODES {
+            let tip = nodes.len() - 1;
+            let position = nodes[tip].position;
+
+            let in_reach = attractors
+                .iter()
+                .any(|a| a.distance_squared(position) <= influence * influence);
+
+            if in_reach || attractors.is_empty() || (position - start).dot(direction) > top {
+                break;
+            }
+
+            nodes[tip].children.push(tip + 1);
+            nodes.push(Node {
+                position: position + direction * step,
+                parent: Some(tip),
+                children: Vec::new(),
+                radius: 0.0,
+            });
+        }
+
+        let mut grid = Grid::new(influence);
+
+        for (i, node) in nodes.iter().enumerate() {
+            grid.insert(i, node.position);
+        }
+
+        for _ in 0..MAX_ITERATIONS {
+            if attractors.is_empty() || nodes.len() >= MAX_NODES {
+                break;
+            }
+
+            let mut pulls = vec![Vec3::ZERO; nodes.len()];
+
+            for attractor in attractors.iter() {
+                let closest = grid
+                    .near(*attractor)
+                    .map(|i| (i, nodes[i].position.distance_squared(*attractor)))
+                    .filter(|(_, distance)| *distance <= influence * influence)
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+
+                if let Some((i, _)) = closest {
+                    pulls[i] += (*attractor - nodes[i].position).normalize_or_zero();
+                }
+            }
+
+            let mut grown = false;
+
+            for (i, pull) in pulls.into_iter().enumerate() {
+                let pull = pull.normalize_or_zero();
+
+                if pull == Vec3::ZERO || nodes.len() >= MAX_NODES {
+                    continue;
+                }
+
+                let index = nodes.len();
+                let position = nodes[i].position + pull * step;
+
+                let duplicate = nodes[i]
+                    .children
+                    .iter()
+                    .any(|&child| nodes[child].position.distance(position) < step * 0.1);
+
+                if duplicate {
+                    continue;
+                }
+
+                nodes[i].children.push(index);
+                nodes.push(Node {
+                    position,
+                    parent: Some(i),
+                    children: Vec::new(),
+                    radius: 0.0,
+                });
+                grid.insert(index, position);
+
+                grown = true;
+            }
+
+            attractors.retain(|attractor| {
+                grid.near(*attractor)
+                    .all(|i| nodes[i].position.distance_squared(*attractor) > kill * kill)
+            });
+
+            if !grown {
+                break;
+            }
+        }
+
+        let tip_radius = self.radius.eval(ctx).max(0.0) * ctx.scale;
+
+        for i in (0..nodes.len()).rev() {
+            let radius = if nodes[i].children.is_empty() {
+                tip_radius
+            } else {
+                nodes[i]
+                    .children
+                    .iter()
+                    .map(|&child| nodes[child].radius.powf(PIPE_EXPONENT))
+                    .sum::<f32>()
+                    .powf(1.0 / PIPE_EXPONENT)
+            };
+
+            nodes[i].radius = radius;
+        }
+
+        nodes
+    }
+
+    fn chains(nodes: &[Node]) -> Vec<Vec<usize>> {
+        let mut chains = Vec::new();
+        let mut starts = vec![0];
+
+        while let Some(start) = starts.pop() {
+            let mut chain = vec![start];
+
+            if let Some(parent) = nodes[start].parent {
+                chain.insert(0, parent);
+            }
+
+            let mut current = start;
+
+            loop {
+                let mut children = nodes[current].children.clone();
+                children.sort_by(|a, b| nodes[*b].radius.total_cmp(&nodes[*a].radius));
+
+                let Some((&next, rest)) = children.split_first() else {
+                    break;
+                };
+
+                starts.extend_from_slice(rest);
+                chain.push(next);
+                current = next;
+            }
+
+            if chain.len() > 1 {
+                chains.push(chain);
+            }
+        }
+
+        chains
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let up = frame::orthogonalize(direction, up);
+        let nodes = self.skeleton(start, direction, up, &ctx);
+        let chains = Self::chains(&nodes);
+
+        let tessellation = ctx.tessellation;
+        let root_radius = nodes[0].radius.max(f32::EPSILON);
+
+        let first = mesh.vertices.len();
+        let mut tips = Vec::new();
+
+        for (k, chain) in chains.iter().enumerate() {
+            let control = chain.iter().map(|&i| nodes[i].position).collect::<Vec<_>>();
+            let mut radii = chain.iter().map(|&i| nodes[i].radius).collect::<Vec<_>>();
+
+            if k > 0 {
+                radii[0] = radii[1];
+            }
+
+            let heading = (control[1] - control[0]).normalize_or_zero();
+            let heading = if heading == Vec3::ZERO {
+                direction
+            } else {
+                heading
+            };
+            let curve = Curve::new(
+                control,
+                heading,
+                frame::transport(up, direction, heading),
+                0.0,
+            );
+
+            let radius = radii[0];
+            let points =
+                curve.adaptive(tessellation.ring_angle(radius), tessellation.curve_samples);
+
+            let radius_at = |point: &CurvePoint| {
+                let x = point.t * (radii.len() - 1) as f32;
+                let i = (x.floor() as usize).min(radii.len() - 2);
+                let x = x - i as f32;
+
+                radii[i] * (1.0 - x) + radii[i + 1] * x
+            };
+
+            let radial = tessellation.radial(radius);
+            let ring_start = mesh.vertices.len();
+
+            tube::tube(mesh, &points, radial, radius_at);
+
+            let rings = mesh.vertices[ring_start..].chunks_mut(radial as usize + 1);
+
+            for (ring, point) in rings.zip(points.iter()) {
+                let t = 1.0 - (radius_at(point) / root_radius).clamp(0.0, 1.0);
+                let color = self.color.at(t).to_array();
+
+                for vertex in ring {
+                    vertex.color = color;
+                }
+            }
+
+            if nodes[*chain.last().unwrap()].children.is_empty() {
+                tips.push((*points.last().unwrap(), *radii.last().unwrap()));
+            }
+        }
+
+        if chains.is_empty() {
+            let point = CurvePoint {
+                position: start,
+                direction,
+                up,
+                distance: 0.0,
+                t: 0.0,
+            };
+
+            tips.push((point, ctx.parent_radius));
+        }
+
+        let vertices = first..mesh.vertices.len();
+        mesh.tag(vertices.clone(), Organ::Branch);
+        ctx.apply_material(mesh, vertices, Material::Bark(self.bark.clone()));
+
+        for (i, (tip, radius)) in tips.iter().enumerate() {
+            let ctx = GenerateContext {
+                index: i as u32,
+                t: i as f32 / (tips.len().max(2) - 1) as f32,
+                parent_radius: *radius,
+                ..ctx.child()
+            };
+
+            self.end
+                .generate_mesh(mesh, tip.position, tip.direction, tip.up, ctx);
+        }
+    }
+}
+
+pub enum ColonizationMessage {
+    SetEnvelope(Envelope),
+    SetAttractors(Gene),
+    SetWidth(Gene),
+    SetHeight(Gene),
+    SetTrunk(Gene),
+    SetInfluence(Gene),
+    SetKill(Gene),
+    SetStep(Gene),
+    SetRadius(Gene),
+    SetColor(Gradient),
+    SetBark(BarkPattern),
+    ChangeEnd(Box<PlantMessage>),
+}
+
+impl ColonizationMessage {
+    pub fn handle(self, rng: &mut impl Rng, colonization: &mut Colonization) {
+        match self {
+            Self::SetEnvelope(x) => colonization.envelope = x,
+            Self::SetAttractors(x) => colonization.attractors = x,
+            Self::SetWidth(x) => colonization.width = x,
+            Self::SetHeight(x) => colonization.height = x,
+            Self::SetTrunk(x) => colonization.trunk = x,
+            Self::SetInfluence(x) => colonization.influence = x,
+            Self::SetKill(x) => colonization.kill = x,
+            Self::SetStep(x) => colonization.step = x,
+            Self::SetRadius(x) => colonization.radius = x,
+            Self::SetColor(x) => colonization.color = x,
+            Self::SetBark(x) => colonization.bark = x,
+            Self::ChangeEnd(msg) => msg.handle(rng, &mut colonization.end),
+        }
+    }
+}
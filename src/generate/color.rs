@@ -0,0 +1,135 @@
+use glam::Vec3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl Color {
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Self { r, g, b }
+    }
+
+    pub fn near(rng: &mut impl Rng, base: Self, spread: f32) -> Self {
+        let mut color = base;
+        color.mutate(rng, spread);
+        color
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.r = (self.r + rng.gen_range(-0.5..0.5) * variance).clamp(0.0, 1.0);
+        self.g = (self.g + rng.gen_range(-0.5..0.5) * variance).clamp(0.0, 1.0);
+        self.b = (self.b + rng.gen_range(-0.5..0.5) * variance).clamp(0.0, 1.0);
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        self.lerp(other, rng.gen_range(0.0..1.0))
+    }
+
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self::rgb(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+        )
+    }
+
+    pub fn to_array(self) -> [f32; 3] {
+        [self.r, self.g, self.b]
+    }
+
+    pub fn vec3(&self) -> Vec3 {
+        Vec3::new(self.r, self.g, self.b)
+    }
+
+    pub fn hex(&self) -> String {
+        let [r, g, b] = [self.r, self.g, self.b].map(|x| (x.clamp(0.0, 1.0) * 255.0).round() as u8);
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#')?;
+
+        if hex.len() != 6 {
+            return None;
+        }
+
+        let channel = |i: usize| {
+            u8::from_str_radix(hex.get(i..i + 2)?, 16)
+                .ok()
+                .map(|x| x as f32 / 255.0)
+        };
+
+        Some(Self::rgb(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    pub fn view(&self, name: &str, callback: Callback<Color>) -> Html {
+        let current = *self;
+        let oninput = callback.reform(move |event: InputEvent| {
+            let value = event.target_unchecked_into::<HtmlInputElement>().value();
+            Self::from_hex(&value).unwrap_or(current)
+        });
+
+        html! {
+            <div class="property">
+                { name }
+                <input
+                    class="color-input"
+                    type="color"
+                    value={ self.hex() }
+                    { oninput }
+                />
+            </div>
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Gradient {
+    pub base: Color,
+    pub tip: Color,
+}
+
+impl Gradient {
+    pub fn near(rng: &mut impl Rng, base: Color, spread: f32) -> Self {
+        let base = Color::near(rng, base, spread);
+
+        Self {
+            base,
+            tip: Color::near(rng, base, spread),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.base.mutate(rng, variance);
+        self.tip.mutate(rng, variance);
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            base: self.base.crossover(&other.base, rng),
+            tip: self.tip.crossover(&other.tip, rng),
+        }
+    }
+
+    pub fn at(&self, t: f32) -> Color {
+        self.base.lerp(&self.tip, t.clamp(0.0, 1.0))
+    }
+
+    pub fn view(&self, callback: &Callback<Gradient>) -> Html {
+        let gradient = *self;
+
+        html! {
+            <>
+                { self.base.view("Base Color", callback.reform(move |base| Gradient { base, ..gradient })) }
+                { self.tip.view("Tip Color", callback.reform(move |tip| Gradient { tip, ..gradient })) }
+            </>
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use std::f32::consts::PI;
+
+use glam::{Quat, Vec3};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::mesh::{Mesh, Organ};
+
+use super::{
+    curve::{Curve, CurvePoint},
+    frame,
+    leaf::{self, Leaf, LeafMessage},
+    Gene, GenerateContext, Material,
+};
+
+const MAX_LEAFLETS: usize = 32;
+
+const SECONDARY_LENGTH: f32 = 0.4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Arrangement {
+    Pinnate,
+    Palmate,
+}
+
+impl Arrangement {
+    pub const ALL: [Self; 2] = [Self::Pinnate, Self::Palmate];
+
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self::ALL[rng.gen_range(0..Self::ALL.len())]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Pinnate => "Pinnate",
+            Self::Palmate => "Palmate",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CompoundLeaf {
+    pub arrangement: Arrangement,
+    pub length: Gene,
+    pub radius: Gene,
+    pub bend: Gene,
+    pub leaflets: Gene,
+    pub angle: Gene,
+    pub falloff: Gene,
+    pub bipinnate: bool,
+    pub leaflet: Leaf,
+}
+
+impl CompoundLeaf {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            arrangement: Arrangement::new(rng),
+            length: rng.gen_range(0.2..1.0).into(),
+            radius: rng.gen_range(0.005..0.02).into(),
+            bend: rng.gen_range(-0.5..0.5).into(),
+            leaflets: rng.gen_range(2.0..6.0).into(),
+            angle: rng.gen_range(0.3..1.2).into(),
+            falloff: rng.gen_range(0.0..0.6).into(),
+            bipinnate: rng.gen_range(0.0..1.0) < 0.2,
+            leaflet: Leaf::new(rng),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.length.mutate(rng, 0.25, variance);
+        self.radius.mutate(rng, 0.005, variance);
+        self.bend.mutate(rng, 0.25, variance);
+        self.leaflets.mutate(rng, 1.0, variance);
+        self.angle.mutate(rng, 0.25, variance);
+        self.falloff.mutate(rng, 0.25, variance);
+        self.leaflet.mutate(rng, variance);
+
+        if rng.gen_range(0.0..1.0) < 0.1 * variance {
+            self.arrangement = Arrangement::new(rng);
+        }
+
+        if rng.gen_range(0.0..1.0) < 0.1 * variance {
+            self.bipinnate = !self.bipinnate;
+        }
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            arrangement: if rng.gen() {
+                self.arrangement
+            } else {
+                other.arrangement
+            },
+            length: self.length.crossover(&other.length, rng),
+            radius: self.radius.crossover(&other.radius, rng),
+            bend: self.bend.crossover(&other.bend, rng),
+            leaflets: self.leaflets.crossover(&other.leaflets, rng),
+            angle: self.angle.crossover(&other.angle, rng),
+            falloff: self.falloff.crossover(&other.falloff, rng),
+            bipinnate: if rng.gen() {
+                self.bipinnate
+            } else {
+                other.bipinnate
+            },
+            leaflet: self.leaflet.crossover(&other.leaflet, rng),
+        }
+    }
+
+    pub fn view(&self, callback: &Callback<CompoundLeafMessage>) -> Html {
+        let arrangements = Arrangement::ALL.into_iter().map(|arrangement| {
+            let onclick = callback.reform(move |_| CompoundLeafMessage::SetArrangement(arrangement));
+
+            html! {
+                <option { onclick } selected={ arrangement == self.arrangement }>{ arrangement.name() }</option>
+            }
+        });
+
+        let bipinnate = !self.bipinnate;
+
+        html! {
+            <>
+                <div class="property">
+                    { "Arrangement" }
+                    <select>{ for arrangements }</select>
+                </div>
+                { self.length.view("Rachis Length", 0.1, 2.0, callback.reform(CompoundLeafMessage::SetLength)) }
+                { self.radius.view("Rachis Radius", 0.0, 0.05, callback.reform(CompoundLeafMessage::SetRadius)) }
+                { self.bend.view("Rachis Bend", -1.5, 1.5, callback.reform(CompoundLeafMessage::SetBend)) }
+                { self.leaflets.view("Leaflets", 1.0, 12.0, callback.reform(CompoundLeafMessage::SetLeaflets)) }
+                { self.angle.view("Leaflet Angle", 0.0, PI, callback.reform(CompoundLeafMessage::SetAngle)) }
+                { self.falloff.view("Falloff", -1.0, 1.0, callback.reform(CompoundLeafMessage::SetFalloff)) }
+                <div class="property">
+                    { "Bipinnate" }
+                    <input
+                        type="checkbox"
+                        checked={ self.bipinnate }
+                        onclick={ callback.reform(move |_| CompoundLeafMessage::SetBipinnate(bipinnate)) }
+                    />
+                </div>
+                <div class="container">
+                    { self.leaflet.view(&callback.reform(CompoundLeafMessage::ChangeLeaflet)) }
+                </div>
+            </>
+        }
+    }
+
+    fn rachis(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        length: f32,
+        ctx: &GenerateContext,
+    ) -> Curve {
+        let radius = self.radius.eval(ctx) * ctx.scale;
+        let bend = self.bend.eval(ctx);
+
+        let first = mesh.vertices.len();
+        let (curve, _) = leaf::stalk(
+            mesh,
+            start,
+            direction,
+            up,
+            length,
+            bend,
+            radius,
+            0.5,
+            self.leaflet.color.base,
+            ctx,
+        );
+
+        if mesh.vertices.len() > first {
+            let vertices = first..mesh.vertices.len();
+            mesh.tag(vertices.clone(), Organ::Branch);
+            ctx.apply_material(mesh, vertices, Material::Plain);
+        }
+
+        curve
+    }
+
+    fn leaflet(
+        &self,
+        mesh: &mut Mesh,
+        point: &CurvePoint,
+        direction: Vec3,
+        size: f32,
+        secondary: bool,
+        ctx: &GenerateContext,
+    ) {
+        let ctx = GenerateContext {
+            scale: ctx.scale * size.max(0.0),
+            ..ctx.child()
+        };
+
+        if ctx.scale <= 0.0 {
+            return;
+        }
+
+        if self.bipinnate && !secondary {
+            self.pinnate(mesh, point.position, direction, point.up, true, &ctx);
+        } else {
+            self.leaflet
+                .generate(mesh, point.position, direction, point.up, ctx);
+        }
+    }
+
+    fn pinnate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        secondary: bool,
+        ctx: &GenerateContext,
+    ) {
+        let length = match secondary {
+            true => self.length.eval(ctx) * ctx.scale * SECONDARY_LENGTH,
+            false => self.length.eval(ctx) * ctx.scale,
+        };
+        let pairs = (self.leaflets.eval(ctx).round().max(0.0) as usize).min(MAX_LEAFLETS);
+        let angle = self.angle.eval(ctx);
+        let falloff = self.falloff.eval(ctx);
+
+        let curve = self.rachis(mesh, start, direction, up, length, ctx);
+        let points = curve.sample(pairs + 1);
+
+        for (i, point) in points.iter().enumerate().skip(1) {
+            let t = i as f32 / (pairs + 1) as f32;
+            let size = 1.0 - falloff * t;
+
+            let ctx = GenerateContext {
+                index: i as u32,
+                t,
+                ..*ctx
+            };
+
+            if i == points.len() - 1 {
+                self.leaflet(mesh, point, point.direction, size, secondary, &ctx);
+                continue;
+            }
+
+            for side in [-1.0, 1.0] {
+                let rotation = Quat::from_axis_angle(point.up, angle * side);
+                self.leaflet(
+                    mesh,
+                    point,
+                    rotation * point.direction,
+                    size,
+                    secondary,
+                    &ctx,
+                );
+            }
+        }
+    }
+
+    fn palmate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: &GenerateContext,
+    ) {
+        let length = self.length.eval(ctx) * ctx.scale;
+        let amount = (self.leaflets.eval(ctx).round().max(1.0) as usize).min(MAX_LEAFLETS);
+        let spread = self.angle.eval(ctx);
+        let falloff = self.falloff.eval(ctx);
+
+        let curve = self.rachis(mesh, start, direction, up, length, ctx);
+        let tip = curve.sample(1)[1];
+
+        for i in 0..amount {
+            let t = if amount > 1 {
+                i as f32 / (amount - 1) as f32
+            } else {
+                0.5
+            };
+
+            let side = t * 2.0 - 1.0;
+            let size = 1.0 - falloff * side.abs();
+
+            let ctx = GenerateContext {
+                index: i as u32,
+                t,
+                ..*ctx
+            };
+
+            let rotation = Quat::from_axis_angle(tip.up, spread * side / 2.0);
+            self.leaflet(mesh, &tip, rotation * tip.direction, size, false, &ctx);
+        }
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let up = frame::orthogonalize(direction, up);
+
+        match self.arrangement {
+            Arrangement::Pinnate => self.pinnate(mesh, start, direction, up, false, &ctx),
+            Arrangement::Palmate => self.palmate(mesh, start, direction, up, &ctx),
+        }
+    }
+}
+
+pub enum CompoundLeafMessage {
+    SetArrangement(Arrangement),
+    SetLength(Gene),
+    SetRadius(Gene),
+    SetBend(Gene),
+    SetLeaflets(Gene),
+    SetAngle(Gene),
+    SetFalloff(Gene),
+    SetBipinnate(bool),
+    ChangeLeaflet(LeafMessage),
+}
+
+impl CompoundLeafMessage {
+    pub fn handle(self, compound: &mut CompoundLeaf) {
+        match self {
+            Self::SetArrangement(x) => compound.arrangement = x,
+            Self::SetLength(x) => compound.length = x,
+            Self::SetRadius(x) => compound.radius = x,
+            Self::SetBend(x) => compound.bend = x,
+            Self::SetLeaflets(x) => compound.leaflets = x,
+            Self::SetAngle(x) => compound.angle = x,
+            Self::SetFalloff(x) => compound.falloff = x,
+            Self::SetBipinnate(x) => compound.bipinnate = x,
+            Self::ChangeLeaflet(msg) => msg.handle(&mut compound.leaflet),
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::ops::Range;
+
+use glam::Vec3;
+
+use crate::mesh::Mesh;
+
+use super::{tube::Joint, Material, Materials, PlantDna, Tessellation, TessellationSettings};
+
+#[derive(Clone, Copy)]
+pub struct GenerateContext<'a> {
+    pub scale: f32,
+    pub recursion: u32,
+    pub depth: u32,
+    pub index: u32,
+    pub t: f32,
+    pub parent_radius: f32,
+    pub joint: Option<&'a Joint>,
+    pub light: Vec3,
+    pub roots: bool,
+    pub obstacle: Option<&'a Mesh>,
+    pub tessellation: TessellationSettings,
+    pub materials: Option<&'a Materials>,
+    pub scope: Option<&'a Scope<'a>>,
+}
+
+impl<'a> GenerateContext<'a> {
+    pub fn new() -> Self {
+        Self {
+            scale: 1.0,
+            recursion: 0,
+            depth: 0,
+            index: 0,
+            t: 0.0,
+            parent_radius: 0.0,
+            joint: None,
+            light: Vec3::new(0.0, 1.0, 1.0).normalize(),
+            roots: true,
+            obstacle: None,
+            tessellation: Tessellation::default().settings(),
+            materials: None,
+            scope: None,
+        }
+    }
+
+    pub fn child(&self) -> Self {
+        Self {
+            depth: self.depth + 1,
+            joint: None,
+            ..*self
+        }
+    }
+
+    pub fn apply_material(&self, mesh: &mut Mesh, vertices: Range<usize>, material: Material) {
+        if let Some(materials) = self.materials {
+            materials.apply(mesh, vertices, &material);
+        }
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&'a PlantDna> {
+        let mut scope = self.scope;
+
+        while let Some(current) = scope {
+            if current.name == name {
+                return Some(current.dna);
+            }
+
+            scope = current.parent;
+        }
+
+        None
+    }
+}
+
+impl Default for GenerateContext<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Scope<'a> {
+    pub name: &'a str,
+    pub dna: &'a PlantDna,
+    pub parent: Option<&'a Scope<'a>>,
+}
@@ -0,0 +1,160 @@
+use glam::{Quat, Vec3};
+
+use super::frame;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CurvePoint {
+    pub position: Vec3,
+    pub direction: Vec3,
+    pub up: Vec3,
+    pub distance: f32,
+    pub t: f32,
+}
+
+impl CurvePoint {
+    pub fn right(&self) -> Vec3 {
+        self.direction.cross(self.up)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve {
+    pub control: Vec<Vec3>,
+    pub direction: Vec3,
+    pub up: Vec3,
+    pub twist: f32,
+}
+
+impl Curve {
+    pub fn new(control: Vec<Vec3>, direction: Vec3, up: Vec3, twist: f32) -> Self {
+        Self {
+            control,
+            direction,
+            up: frame::orthogonalize(direction, up),
+            twist,
+        }
+    }
+
+    pub fn position(&self, t: f32) -> Vec3 {
+        let segments = self.control.len().saturating_sub(1);
+
+        if segments == 0 {
+            return self.control.first().copied().unwrap_or(Vec3::ZERO);
+        }
+
+        let x = t.clamp(0.0, 1.0) * segments as f32;
+        let i = (x.floor() as usize).min(segments - 1);
+        let x = x - i as f32;
+
+        let p1 = self.control[i];
+        let p2 = self.control[i + 1];
+        let p0 = if i > 0 {
+            self.control[i - 1]
+        } else {
+            p1 * 2.0 - p2
+        };
+        let p3 = if i + 2 < self.control.len() {
+            self.control[i + 2]
+        } else {
+            p2 * 2.0 - p1
+        };
+
+        let x2 = x * x;
+        let x3 = x2 * x;
+
+        0.5 * (p1 * 2.0
+            + (p2 - p0) * x
+            + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * x2
+            + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * x3)
+    }
+
+    pub fn sample(&self, samples: usize) -> Vec<CurvePoint> {
+        let samples = samples.max(1);
+
+        let positions = (0..=samples)
+            .map(|i| self.position(i as f32 / samples as f32))
+            .collect::<Vec<_>>();
+
+        let mut points = Vec::with_capacity(positions.len());
+        let mut direction = self.direction;
+        let mut up = self.up;
+        let mut distance = 0.0;
+
+        for (i, &position) in positions.iter().enumerate() {
+            if i > 0 {
+                let step = position - positions[i - 1];
+                distance += step.length();
+
+                let next = if i < samples {
+                    positions[i + 1] - positions[i - 1]
+                } else {
+                    step
+                };
+
+                let next = next.normalize_or_zero();
+
+                if next != Vec3::ZERO {
+                    up = frame::transport(up, direction, next);
+                    direction = next;
+                }
+
+                up = Quat::from_axis_angle(direction, self.twist * step.length()) * up;
+            }
+
+            points.push(CurvePoint {
+                position,
+                direction,
+                up,
+                distance,
+                t: 0.0,
+            });
+        }
+
+        for point in points.iter_mut() {
+            point.t = if distance > 0.0 {
+                point.distance / distance
+            } else {
+                0.0
+            };
+        }
+
+        points
+    }
+
+    pub fn adaptive(&self, max_angle: f32, samples: usize) -> Vec<CurvePoint> {
+        self.adaptive_spaced(max_angle, f32::INFINITY, samples)
+    }
+
+    pub fn adaptive_spaced(
+        &self,
+        max_angle: f32,
+        max_spacing: f32,
+        samples: usize,
+    ) -> Vec<CurvePoint> {
+        let points = self.sample(samples);
+        let mut kept: Vec<CurvePoint> = Vec::new();
+
+        for (i, point) in points.iter().enumerate() {
+            let keep = match kept.last() {
+                Some(last) => {
+                    let angle = last.direction.angle_between(point.direction);
+                    let twist = last.up.angle_between(point.up);
+
+                    let spacing = point.distance - last.distance;
+
+                    i == points.len() - 1
+                        || angle > max_angle
+                        || twist > max_angle * 2.0
+                        || spacing >= max_spacing
+                }
+                None => true,
+            };
+
+            if keep {
+                kept.push(*point);
+            }
+        }
+
+        kept
+    }
+}
@@ -5,16 +5,18 @@ use rand::{prelude::StdRng, Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use yew::prelude::*;
 
-use crate::{components::Slider, mesh::Mesh};
+use crate::mesh::Mesh;
 
-use super::{PlantDna, PlantMessage};
+use super::{frame, Gene, GenerateContext, PlantDna, PlantMessage};
+
+const MAX_AMOUNT: usize = 1024;
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Distribution {
     pub seed: u64,
-    pub amount: f32,
-    pub min_angle: f32,
-    pub max_angle: f32,
+    pub amount: Gene,
+    pub min_angle: Gene,
+    pub max_angle: Gene,
     pub value: Box<PlantDna>,
 }
 
@@ -25,78 +27,82 @@ impl Distribution {
 
         Self {
             seed: rng.gen(),
-            amount: rng.gen_range(0.5..5.0),
-            min_angle: min_angle.min(max_angle),
-            max_angle: min_angle.max(max_angle),
+            amount: rng.gen_range(0.5..5.0).into(),
+            min_angle: min_angle.min(max_angle).into(),
+            max_angle: min_angle.max(max_angle).into(),
             value: Box::new(PlantDna::new(rng)),
         }
     }
 
     pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
-        self.amount += rng.gen_range(-0.5..0.5) * variance;
-        self.min_angle += rng.gen_range(-0.5..0.5) * variance;
-        self.max_angle += rng.gen_range(-0.5..0.5) * variance;
+        self.amount.mutate(rng, 0.5, variance);
+        self.min_angle.mutate(rng, 0.5, variance);
+        self.max_angle.mutate(rng, 0.5, variance);
         self.value.mutate(rng, variance);
 
-        if self.min_angle > self.max_angle {
-            std::mem::swap(&mut self.min_angle, &mut self.max_angle);
+        if let (Some(min), Some(max)) = (self.min_angle.as_constant(), self.max_angle.as_constant())
+        {
+            if min > max {
+                std::mem::swap(&mut self.min_angle, &mut self.max_angle);
+            }
+        }
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            seed: if rng.gen() { self.seed } else { other.seed },
+            amount: self.amount.crossover(&other.amount, rng),
+            min_angle: self.min_angle.crossover(&other.min_angle, rng),
+            max_angle: self.max_angle.crossover(&other.max_angle, rng),
+            value: Box::new(self.value.crossover(&other.value, rng)),
         }
     }
 
     pub fn view(&self, callback: &Callback<DistributionMessage>) -> Html {
         html! {
             <>
-                <div class="property">
-                    { "Amount" }
-                    <Slider
-                        min=0.5
-                        max=5.0
-                        value={ self.amount }
-                        oninput={ callback.reform(DistributionMessage::SetAmount) }
-                    />
-                </div>
-                <div class="property">
-                    { "Min Angle" }
-                    <Slider
-                        min={ -FRAC_PI_2 }
-                        max={ FRAC_PI_2 }
-                        value={ self.min_angle }
-                        oninput={ callback.reform(DistributionMessage::SetMinAngle) }
-                    />
-                </div>
-                <div class="property">
-                    { "Max Angle" }
-                    <Slider
-                        min={ -FRAC_PI_2 }
-                        max={ FRAC_PI_2 }
-                        value={ self.max_angle }
-                        oninput={ callback.reform(DistributionMessage::SetMaxAngle) }
-                    />
-                </div>
+                { self.amount.view("Amount", 0.5, 5.0, callback.reform(DistributionMessage::SetAmount)) }
+                { self.min_angle.view("Min Angle", -FRAC_PI_2, FRAC_PI_2, callback.reform(DistributionMessage::SetMinAngle)) }
+                { self.max_angle.view("Max Angle", -FRAC_PI_2, FRAC_PI_2, callback.reform(DistributionMessage::SetMaxAngle)) }
                 { self.value.view(&callback.reform(|msg| DistributionMessage::ChangeValue(Box::new(msg)))) }
             </>
         }
     }
 
-    pub fn generate(&self, mesh: &mut Mesh, start: Vec3, direction: Vec3, real_up: Vec3) {
-        if self.amount == 0.0 {
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let amount = self.amount.eval(&ctx);
+        let mut min_angle = self.min_angle.eval(&ctx);
+        let mut max_angle = self.max_angle.eval(&ctx);
+
+        if amount <= 0.0 {
             return;
         }
 
-        let right = real_up.cross(direction).normalize();
-        let up = direction.cross(right).normalize();
+        if min_angle > max_angle {
+            std::mem::swap(&mut min_angle, &mut max_angle);
+        }
+
+        let up = frame::orthogonalize(direction, up);
+        let right = up.cross(direction).normalize();
 
         let mut rng = StdRng::seed_from_u64(self.seed);
 
-        let amount = self.amount.powi(2).round() as usize;
+        let amount = (amount.powi(2).round() as usize).min(MAX_AMOUNT);
 
-        for _ in 0..amount {
+        for i in 0..amount {
             let angle = rng.gen_range(0.0..TAU);
 
-            let h = if self.min_angle == self.max_angle {
-                self.min_angle
+            let h = if min_angle == max_angle {
+                min_angle
             } else {
-                rng.gen_range(self.min_angle..self.max_angle)
+                rng.gen_range(min_angle..max_angle)
             };
 
             let (sinh, cosh) = h.sin_cos();
@@ -105,17 +111,27 @@ impl Distribution {
 
             let d = direction * sinh + up * cos * cosh + right * sin * cosh;
 
-            self.value
-                .generate_mesh(mesh, start, d.normalize(), real_up);
+            let d = d.normalize();
+
+            let ctx = GenerateContext {
+                index: i as u32,
+                t: i as f32 / (amount - 1).max(1) as f32,
+                parent_radius: ctx.parent_radius / (amount as f32).sqrt(),
+                ..ctx.child()
+            };
+
+            let child_up = frame::transport(up, direction, d);
+
+            self.value.generate_mesh(mesh, start, d, child_up, ctx);
         }
     }
 }
 
 pub enum DistributionMessage {
     ChangeValue(Box<PlantMessage>),
-    SetAmount(f32),
-    SetMinAngle(f32),
-    SetMaxAngle(f32),
+    SetAmount(Gene),
+    SetMinAngle(Gene),
+    SetMaxAngle(Gene),
 }
 
 impl DistributionMessage {
@@ -124,12 +140,22 @@ impl DistributionMessage {
             Self::ChangeValue(msg) => msg.handle(rng, &mut distribution.value),
             Self::SetAmount(x) => distribution.amount = x,
             Self::SetMinAngle(x) => {
+                if let (Some(min), Some(max)) =
+                    (x.as_constant(), distribution.max_angle.as_constant())
+                {
+                    distribution.max_angle = max.max(min).into();
+                }
+
                 distribution.min_angle = x;
-                distribution.max_angle = distribution.max_angle.max(x);
             }
             Self::SetMaxAngle(x) => {
+                if let (Some(min), Some(max)) =
+                    (distribution.min_angle.as_constant(), x.as_constant())
+                {
+                    distribution.min_angle = min.min(max).into();
+                }
+
                 distribution.max_angle = x;
-                distribution.min_angle = distribution.min_angle.min(x);
             }
         }
     }
@@ -0,0 +1,427 @@
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+use glam::{Quat, Vec3};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::mesh::{Mesh, Organ, Vertex};
+
+use super::{
+    curve::Curve,
+    frame,
+    leaf::{self, BladeRow},
+    tube, Color, Gene, GenerateContext, Gradient, LeafPattern, LeafShape, Material, PetalPattern,
+};
+
+const LAYER_SCALE: f32 = 0.8;
+
+const LAYER_CUP: f32 = 0.3;
+
+const PETAL_COLUMNS: u32 = 5;
+
+const MAX_PETALS: u32 = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Center {
+    Disc,
+    Stamens,
+}
+
+impl Center {
+    pub const ALL: [Self; 2] = [Self::Disc, Self::Stamens];
+
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self::ALL[rng.gen_range(0..Self::ALL.len())]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Disc => "Disc",
+            Self::Stamens => "Stamens",
+        }
+    }
+}
+
+struct Sheet<'a> {
+    shape: &'a LeafShape,
+    length: f32,
+    width: f32,
+    curl: f32,
+    color: Gradient,
+}
+
+impl Sheet<'_> {
+    fn generate(&self, mesh: &mut Mesh, start: Vec3, direction: Vec3, normal: Vec3, steps: u32) {
+        let right = normal.cross(direction).normalize();
+
+        leaf::blade(mesh, steps, PETAL_COLUMNS, 0.0, self.curl, |x| BladeRow {
+            center: start + direction * x * self.length,
+            right,
+            normal,
+            width: self.shape.width(x) * self.width / 2.0,
+            color: self.color.at(x),
+        });
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Flower {
+    pub petals: Gene,
+    pub layers: Gene,
+    pub petal_length: Gene,
+    pub petal_width: Gene,
+    pub cup: Gene,
+    pub curl: Gene,
+    pub petal_shape: LeafShape,
+    pub sepals: Gene,
+    pub sepal_length: Gene,
+    pub center: Center,
+    pub center_radius: Gene,
+    pub color: Gradient,
+    pub sepal_color: Color,
+    pub center_color: Color,
+    pub pattern: PetalPattern,
+    pub sepal_pattern: LeafPattern,
+}
+
+impl Flower {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            petals: rng.gen_range(3.0..12.0).into(),
+            layers: rng.gen_range(1.0..3.0).into(),
+            petal_length: rng.gen_range(0.1..0.4).into(),
+            petal_width: rng.gen_range(0.05..0.2).into(),
+            cup: rng.gen_range(0.0..1.0).into(),
+            curl: rng.gen_range(-0.5..0.5).into(),
+            petal_shape: LeafShape::new(rng),
+            sepals: rng.gen_range(0.0..6.0).into(),
+            sepal_length: rng.gen_range(0.05..0.2).into(),
+            center: Center::new(rng),
+            center_radius: rng.gen_range(0.02..0.1).into(),
+            color: Gradient::near(rng, Color::rgb(1.0, 1.0, 1.0), 0.2),
+            sepal_color: Color::near(rng, Color::rgb(0.3, 0.6, 0.2), 0.2),
+            center_color: Color::near(rng, Color::rgb(0.9, 0.7, 0.1), 0.4),
+            pattern: PetalPattern::new(rng),
+            sepal_pattern: LeafPattern::new(rng),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.petals.mutate(rng, 2.0, variance);
+        self.layers.mutate(rng, 1.0, variance);
+        self.petal_length.mutate(rng, 0.1, variance);
+        self.petal_width.mutate(rng, 0.05, variance);
+        self.cup.mutate(rng, 0.25, variance);
+        self.curl.mutate(rng, 0.25, variance);
+        self.petal_shape.mutate(rng, variance);
+        self.sepals.mutate(rng, 1.0, variance);
+        self.sepal_length.mutate(rng, 0.05, variance);
+        self.center_radius.mutate(rng, 0.02, variance);
+        self.color.mutate(rng, variance);
+        self.sepal_color.mutate(rng, variance);
+        self.center_color.mutate(rng, variance);
+        self.pattern.mutate(rng, variance);
+        self.sepal_pattern.mutate(rng, variance);
+
+        if rng.gen_range(0.0..1.0) < 0.1 * variance {
+            self.center = Center::new(rng);
+        }
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            petals: self.petals.crossover(&other.petals, rng),
+            layers: self.layers.crossover(&other.layers, rng),
+            petal_length: self.petal_length.crossover(&other.petal_length, rng),
+            petal_width: self.petal_width.crossover(&other.petal_width, rng),
+            cup: self.cup.crossover(&other.cup, rng),
+            curl: self.curl.crossover(&other.curl, rng),
+            petal_shape: if rng.gen() {
+                self.petal_shape.clone()
+            } else {
+                other.petal_shape.clone()
+            },
+            sepals: self.sepals.crossover(&other.sepals, rng),
+            sepal_length: self.sepal_length.crossover(&other.sepal_length, rng),
+            center: if rng.gen() { self.center } else { other.center },
+            center_radius: self.center_radius.crossover(&other.center_radius, rng),
+            color: self.color.crossover(&other.color, rng),
+            sepal_color: self.sepal_color.crossover(&other.sepal_color, rng),
+            center_color: self.center_color.crossover(&other.center_color, rng),
+            pattern: if rng.gen() {
+                self.pattern.clone()
+            } else {
+                other.pattern.clone()
+            },
+            sepal_pattern: if rng.gen() {
+                self.sepal_pattern.clone()
+            } else {
+                other.sepal_pattern.clone()
+            },
+        }
+    }
+
+    pub fn view(&self, callback: &Callback<FlowerMessage>) -> Html {
+        let centers = Center::ALL.into_iter().map(|center| {
+            let onclick = callback.reform(move |_| FlowerMessage::SetCenter(center));
+
+            html! {
+                <option { onclick } selected={ center == self.center }>{ center.name() }</option>
+            }
+        });
+
+        html! {
+            <>
+                { self.petals.view("Petals", 1.0, 24.0, callback.reform(FlowerMessage::SetPetals)) }
+                { self.layers.view("Layers", 1.0, 5.0, callback.reform(FlowerMessage::SetLayers)) }
+                { self.petal_length.view("Petal Length", 0.02, 0.5, callback.reform(FlowerMessage::SetPetalLength)) }
+                { self.petal_width.view("Petal Width", 0.01, 0.3, callback.reform(FlowerMessage::SetPetalWidth)) }
+                { self.cup.view("Cup", -0.5, FRAC_PI_2, callback.reform(FlowerMessage::SetCup)) }
+                { self.curl.view("Curl", -1.0, 1.0, callback.reform(FlowerMessage::SetCurl)) }
+                { self.petal_shape.view(&callback.reform(FlowerMessage::SetPetalShape)) }
+                { self.sepals.view("Sepals", 0.0, 12.0, callback.reform(FlowerMessage::SetSepals)) }
+                { self.sepal_length.view("Sepal Length", 0.01, 0.3, callback.reform(FlowerMessage::SetSepalLength)) }
+                <div class="property">
+                    { "Center" }
+                    <select>{ for centers }</select>
+                </div>
+                { self.center_radius.view("Center Radius", 0.0, 0.2, callback.reform(FlowerMessage::SetCenterRadius)) }
+                { self.color.view(&callback.reform(FlowerMessage::SetColor)) }
+                { self.sepal_color.view("Sepal Color", callback.reform(FlowerMessage::SetSepalColor)) }
+                { self.center_color.view("Center Color", callback.reform(FlowerMessage::SetCenterColor)) }
+                { self.pattern.view(&callback.reform(FlowerMessage::SetPattern)) }
+                { self.sepal_pattern.view(&callback.reform(FlowerMessage::SetSepalPattern)) }
+            </>
+        }
+    }
+
+    fn disc(&self, mesh: &mut Mesh, start: Vec3, axis: Vec3, up: Vec3, radius: f32, radial: u32) {
+        let right = up.cross(axis).normalize();
+        let rings = (radial / 2).max(2);
+        let color = self.center_color.to_array();
+
+        for i in 0..=rings {
+            let theta = i as f32 / rings as f32 * FRAC_PI_2;
+            let (ring, height) = (theta.sin() * radius, theta.cos() * radius * 0.5);
+
+            let first = mesh.vertices.len() as u32;
+            mesh.seams.push([first, first + radial]);
+
+            for j in 0..=radial {
+                let (sin, cos) = (j as f32 / radial as f32 * TAU).sin_cos();
+                let p = start + axis * height + (up * cos + right * sin) * ring;
+
+                mesh.vertices.push(Vertex {
+                    position: p.into(),
+                    normal: [0.0; 3],
+                    uv: [0.5, 0.0],
+                    color,
+                    ..Default::default()
+                });
+            }
+
+            if i > 0 {
+                let previous = first - (radial + 1);
+
+                for j in 0..radial {
+                    mesh.indices.push(previous + j);
+                    mesh.indices.push(first + j);
+                    mesh.indices.push(previous + j + 1);
+
+                    mesh.indices.push(previous + j + 1);
+                    mesh.indices.push(first + j);
+                    mesh.indices.push(first + j + 1);
+                }
+            }
+        }
+    }
+
+    fn stamens(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        axis: Vec3,
+        up: Vec3,
+        radius: f32,
+        amount: u32,
+    ) {
+        let length = radius * 3.0;
+        let thickness = radius * 0.08;
+        let color = self.center_color.to_array();
+
+        for i in 0..amount {
+            let angle = i as f32 / amount as f32 * TAU;
+            let out = Quat::from_axis_angle(axis, angle) * up;
+            let direction = (axis + out * 0.4).normalize();
+
+            let control = vec![start, start + direction * length];
+            let curve = Curve::new(control, direction, axis, 0.0);
+            let points = curve.sample(4);
+
+            let first = mesh.vertices.len();
+
+            tube::tube(mesh, &points, 3, |point| {
+                let anther = ((point.t - 0.8) / 0.2).clamp(0.0, 1.0);
+                thickness * (1.0 + anther * 2.0)
+            });
+
+            for vertex in mesh.vertices[first..].iter_mut() {
+                vertex.uv = [0.5, 0.0];
+                vertex.color = color;
+            }
+        }
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let petals = (self.petals.eval(&ctx).round().max(1.0) as u32).min(MAX_PETALS);
+        let layers = self.layers.eval(&ctx).round().clamp(1.0, 5.0) as u32;
+        let petal_length = self.petal_length.eval(&ctx) * ctx.scale;
+        let petal_width = self.petal_width.eval(&ctx) * ctx.scale;
+        let cup = self.cup.eval(&ctx);
+        let curl = self.curl.eval(&ctx);
+        let sepals = (self.sepals.eval(&ctx).round().max(0.0) as u32).min(MAX_PETALS);
+        let sepal_length = self.sepal_length.eval(&ctx) * ctx.scale;
+        let center_radius = self.center_radius.eval(&ctx) * ctx.scale;
+
+        let steps = ctx
+            .tessellation
+            .leaf_steps
+            .max(self.petal_shape.resolution());
+        let up = frame::orthogonalize(direction, up);
+
+        let first = mesh.vertices.len();
+
+        let orient = |angle: f32, cup: f32| {
+            let out = Quat::from_axis_angle(direction, angle) * up;
+            let (sin, cos) = cup.sin_cos();
+
+            (out * cos + direction * sin, direction * cos - out * sin)
+        };
+
+        let sepal_shape = LeafShape::Lanceolate;
+        let sepal = Sheet {
+            shape: &sepal_shape,
+            length: sepal_length,
+            width: sepal_length * 0.5,
+            curl: 0.2,
+            color: Gradient {
+                base: self.sepal_color,
+                tip: self.sepal_color,
+            },
+        };
+
+        for i in 0..sepals {
+            let angle = (i as f32 + 0.5) / sepals as f32 * TAU;
+            let (out, normal) = orient(angle, cup - 0.5);
+
+            sepal.generate(mesh, start, out, normal, steps);
+        }
+
+        if sepals > 0 {
+            let vertices = first..mesh.vertices.len();
+            mesh.tag(vertices.clone(), Organ::Leaf);
+            ctx.apply_material(mesh, vertices, Material::Leaf(self.sepal_pattern.clone()));
+        }
+
+        let first = mesh.vertices.len();
+
+        for layer in 0..layers {
+            let scale = LAYER_SCALE.powi(layer as i32);
+
+            let sheet = Sheet {
+                shape: &self.petal_shape,
+                length: petal_length * scale,
+                width: petal_width * scale,
+                curl,
+                color: self.color,
+            };
+
+            let offset = layer as f32 * PI / petals as f32;
+
+            for i in 0..petals {
+                let angle = i as f32 / petals as f32 * TAU + offset;
+                let (out, normal) = orient(angle, cup + layer as f32 * LAYER_CUP);
+
+                let start = start + direction * layer as f32 * petal_width * 0.02;
+
+                sheet.generate(mesh, start, out, normal, steps);
+            }
+        }
+
+        let vertices = first..mesh.vertices.len();
+        ctx.apply_material(mesh, vertices, Material::Petal(self.pattern.clone()));
+
+        let center = mesh.vertices.len();
+
+        if center_radius > 0.0 {
+            let start = start + direction * petal_width * 0.02 * layers as f32;
+
+            match self.center {
+                Center::Disc => {
+                    let radial = ctx.tessellation.radial(center_radius).max(6);
+                    self.disc(mesh, start, direction, up, center_radius, radial);
+                }
+                Center::Stamens => {
+                    let amount = (petals * 2).max(4);
+                    self.stamens(mesh, start, direction, up, center_radius, amount);
+                }
+            }
+        }
+
+        let vertices = center..mesh.vertices.len();
+        ctx.apply_material(mesh, vertices, Material::Plain);
+
+        mesh.tag(first..mesh.vertices.len(), Organ::Flower);
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+pub enum FlowerMessage {
+    SetPetals(Gene),
+    SetLayers(Gene),
+    SetPetalLength(Gene),
+    SetPetalWidth(Gene),
+    SetCup(Gene),
+    SetCurl(Gene),
+    SetPetalShape(LeafShape),
+    SetSepals(Gene),
+    SetSepalLength(Gene),
+    SetCenter(Center),
+    SetCenterRadius(Gene),
+    SetColor(Gradient),
+    SetSepalColor(Color),
+    SetCenterColor(Color),
+    SetPattern(PetalPattern),
+    SetSepalPattern(LeafPattern),
+}
+
+impl FlowerMessage {
+    pub fn handle(self, flower: &mut Flower) {
+        match self {
+            Self::SetPetals(x) => flower.petals = x,
+            Self::SetLayers(x) => flower.layers = x,
+            Self::SetPetalLength(x) => flower.petal_length = x,
+            Self::SetPetalWidth(x) => flower.petal_width = x,
+            Self::SetCup(x) => flower.cup = x,
+            Self::SetCurl(x) => flower.curl = x,
+            Self::SetPetalShape(x) => flower.petal_shape = x,
+            Self::SetSepals(x) => flower.sepals = x,
+            Self::SetSepalLength(x) => flower.sepal_length = x,
+            Self::SetCenter(x) => flower.center = x,
+            Self::SetCenterRadius(x) => flower.center_radius = x,
+            Self::SetColor(x) => flower.color = x,
+            Self::SetSepalColor(x) => flower.sepal_color = x,
+            Self::SetCenterColor(x) => flower.center_color = x,
+            Self::SetPattern(x) => flower.pattern = x,
+            Self::SetSepalPattern(x) => flower.sepal_pattern = x,
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use glam::{Quat, Vec3};
+
+pub fn orthogonalize(direction: Vec3, up: Vec3) -> Vec3 {
+    let up = up - direction * direction.dot(up);
+
+    if up.length_squared() > f32::EPSILON {
+        up.normalize()
+    } else {
+        direction.any_orthonormal_vector()
+    }
+}
+
+pub fn transport(up: Vec3, from: Vec3, to: Vec3) -> Vec3 {
+    orthogonalize(to, Quat::from_rotation_arc(from, to) * up)
+}
@@ -0,0 +1,321 @@
+use std::f32::consts::{PI, TAU};
+
+use glam::Vec3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::mesh::{Mesh, Organ, Vertex};
+
+use super::{frame, leaf, Color, Gene, GenerateContext, Gradient, Material, SkinPattern};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FruitShape {
+    Sphere,
+    Ellipsoid,
+    Pod,
+    Drupe,
+}
+
+impl FruitShape {
+    pub const ALL: [Self; 4] = [Self::Sphere, Self::Ellipsoid, Self::Pod, Self::Drupe];
+
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self::ALL[rng.gen_range(0..Self::ALL.len())]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sphere => "Sphere",
+            Self::Ellipsoid => "Ellipsoid",
+            Self::Pod => "Pod",
+            Self::Drupe => "Drupe",
+        }
+    }
+
+    fn surface(&self, t: f32, angle: f32, elongation: f32) -> (f32, Vec3) {
+        let theta = t * PI;
+        let (sin, cos) = angle.sin_cos();
+
+        match self {
+            Self::Sphere => (1.0 - theta.cos(), Vec3::new(cos, sin, 0.0) * theta.sin()),
+            Self::Ellipsoid => (
+                (1.0 - theta.cos()) * elongation,
+                Vec3::new(cos, sin, 0.0) * theta.sin(),
+            ),
+            Self::Pod => {
+                let radius = theta.sin().max(0.0).powf(0.4);
+                let along = (1.0 - theta.cos()) * elongation * 2.0;
+
+                (along, Vec3::new(cos * 0.5, sin, 0.0) * radius)
+            }
+            Self::Drupe => {
+                let seam = 1.0 - 0.15 * (-(angle - PI).powi(2) * 4.0).exp();
+                let tip = 1.0 + 0.15 * (-(1.0 - t).powi(2) * 50.0).exp();
+
+                (
+                    (1.0 - theta.cos()) * elongation.sqrt() * tip,
+                    Vec3::new(cos, sin, 0.0) * theta.sin() * seam,
+                )
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fruit {
+    pub shape: FruitShape,
+    pub size: Gene,
+    pub elongation: Gene,
+    pub ridges: Gene,
+    pub ridge_depth: Gene,
+    pub stalk_length: Gene,
+    pub droop: Gene,
+    pub color: Gradient,
+    pub stalk_color: Color,
+    pub skin: SkinPattern,
+}
+
+impl Fruit {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            shape: FruitShape::new(rng),
+            size: rng.gen_range(0.03..0.2).into(),
+            elongation: rng.gen_range(0.8..2.0).into(),
+            ridges: rng.gen_range(0.0..8.0).into(),
+            ridge_depth: rng.gen_range(0.0..0.15).into(),
+            stalk_length: rng.gen_range(0.0..0.2).into(),
+            droop: rng.gen_range(0.0..1.0).into(),
+            color: Gradient::near(rng, Color::rgb(1.0, 1.0, 1.0), 0.2),
+            stalk_color: Color::near(rng, Color::rgb(0.4, 0.5, 0.2), 0.2),
+            skin: SkinPattern::new(rng),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.size.mutate(rng, 0.05, variance);
+        self.elongation.mutate(rng, 0.25, variance);
+        self.ridges.mutate(rng, 1.0, variance);
+        self.ridge_depth.mutate(rng, 0.05, variance);
+        self.stalk_length.mutate(rng, 0.05, variance);
+        self.droop.mutate(rng, 0.25, variance);
+        self.color.mutate(rng, variance);
+        self.stalk_color.mutate(rng, variance);
+        self.skin.mutate(rng, variance);
+
+        if rng.gen_range(0.0..1.0) < 0.1 * variance {
+            self.shape = FruitShape::new(rng);
+        }
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            shape: if rng.gen() { self.shape } else { other.shape },
+            size: self.size.crossover(&other.size, rng),
+            elongation: self.elongation.crossover(&other.elongation, rng),
+            ridges: self.ridges.crossover(&other.ridges, rng),
+            ridge_depth: self.ridge_depth.crossover(&other.ridge_depth, rng),
+            stalk_length: self.stalk_length.crossover(&other.stalk_length, rng),
+            droop: self.droop.crossover(&other.droop, rng),
+            color: self.color.crossover(&other.color, rng),
+            stalk_color: self.stalk_color.crossover(&other.stalk_color, rng),
+            skin: if rng.gen() {
+                self.skin.clone()
+            } else {
+                other.skin.clone()
+            },
+        }
+    }
+
+    pub fn view(&self, callback: &Callback<FruitMessage>) -> Html {
+        let shapes = FruitShape::ALL.into_iter().map(|shape| {
+            let onclick = callback.reform(move |_| FruitMessage::SetShape(shape));
+
+            html! {
+                <option { onclick } selected={ shape == self.shape }>{ shape.name() }</option>
+            }
+        });
+
+        html! {
+            <>
+                <div class="property">
+                    { "Shape" }
+                    <select>{ for shapes }</select>
+                </div>
+                { self.size.view("Size", 0.01, 0.5, callback.reform(FruitMessage::SetSize)) }
+                { self.elongation.view("Elongation", 0.25, 4.0, callback.reform(FruitMessage::SetElongation)) }
+                { self.ridges.view("Ridges", 0.0, 16.0, callback.reform(FruitMessage::SetRidges)) }
+                { self.ridge_depth.view("Ridge Depth", 0.0, 0.5, callback.reform(FruitMessage::SetRidgeDepth)) }
+                { self.stalk_length.view("Stalk Length", 0.0, 0.5, callback.reform(FruitMessage::SetStalkLength)) }
+                { self.droop.view("Droop", 0.0, 1.0, callback.reform(FruitMessage::SetDroop)) }
+                { self.color.view(&callback.reform(FruitMessage::SetColor)) }
+                { self.stalk_color.view("Stalk Color", callback.reform(FruitMessage::SetStalkColor)) }
+                { self.skin.view(&callback.reform(FruitMessage::SetSkin)) }
+            </>
+        }
+    }
+
+    fn stalk(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        size: f32,
+        ctx: &GenerateContext,
+    ) -> (Vec3, Vec3, Vec3) {
+        let length = self.stalk_length.eval(ctx) * ctx.scale;
+        let droop = self.droop.eval(ctx).clamp(0.0, 1.0);
+
+        let hanging = direction.lerp(-Vec3::Y, droop).normalize_or_zero();
+        let hanging = if hanging == Vec3::ZERO {
+            direction
+        } else {
+            hanging
+        };
+
+        if length <= 0.0 {
+            return (start, hanging, frame::transport(up, direction, hanging));
+        }
+
+        let bend = direction.angle_between(hanging);
+        let toward = frame::orthogonalize(direction, hanging);
+        let radius = size * 0.08;
+
+        let first = mesh.vertices.len();
+        let (_, points) = leaf::stalk(
+            mesh,
+            start,
+            direction,
+            toward,
+            length,
+            bend,
+            radius,
+            0.0,
+            self.stalk_color,
+            ctx,
+        );
+
+        let vertices = first..mesh.vertices.len();
+        mesh.tag(vertices.clone(), Organ::Branch);
+        ctx.apply_material(mesh, vertices, Material::Plain);
+
+        let Some(end) = points.last() else {
+            return (start, hanging, frame::transport(up, direction, hanging));
+        };
+
+        (
+            end.position,
+            end.direction,
+            frame::transport(up, direction, end.direction),
+        )
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let size = self.size.eval(&ctx) * ctx.scale;
+        let elongation = self.elongation.eval(&ctx).max(0.1);
+        let ridges = self.ridges.eval(&ctx).round().max(0.0);
+        let ridge_depth = self.ridge_depth.eval(&ctx).clamp(0.0, 1.0);
+
+        if size <= 0.0 {
+            return;
+        }
+
+        let (top, axis, up) = self.stalk(mesh, start, direction, up, size, &ctx);
+
+        let first = mesh.vertices.len();
+
+        let up = frame::orthogonalize(axis, up);
+        let right = axis.cross(up);
+
+        let radial = ctx.tessellation.radial(size).max(6);
+        let rings = (ctx.tessellation.leaf_steps).max(radial / 2);
+
+        for i in 0..=rings {
+            let t = i as f32 / rings as f32;
+            let ring = mesh.vertices.len() as u32;
+            let color = self.color.at(t).to_array();
+            mesh.seams.push([ring, ring + radial]);
+
+            for j in 0..=radial {
+                let u = j as f32 / radial as f32;
+                let angle = u * TAU;
+
+                let (along, across) = self.shape.surface(t, angle, elongation);
+                let ridge = 1.0 - ridge_depth * (0.5 - 0.5 * (angle * ridges).cos());
+                let across = across * ridge;
+
+                let p = top + (axis * along + right * across.x + up * across.y) * size;
+
+                mesh.vertices.push(Vertex {
+                    position: p.into(),
+                    normal: [0.0; 3],
+                    uv: [u, t],
+                    color,
+                    ..Default::default()
+                });
+            }
+
+            if i > 0 {
+                let previous = ring - (radial + 1);
+
+                for j in 0..radial {
+                    let i0 = ring + j;
+                    let i1 = ring + j + 1;
+                    let i2 = previous + j;
+                    let i3 = previous + j + 1;
+
+                    mesh.indices.push(i0);
+                    mesh.indices.push(i2);
+                    mesh.indices.push(i1);
+
+                    mesh.indices.push(i1);
+                    mesh.indices.push(i2);
+                    mesh.indices.push(i3);
+                }
+            }
+        }
+
+        let vertices = first..mesh.vertices.len();
+        mesh.tag(vertices.clone(), Organ::Fruit);
+        ctx.apply_material(mesh, vertices, Material::Skin(self.skin.clone()));
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+pub enum FruitMessage {
+    SetShape(FruitShape),
+    SetSize(Gene),
+    SetElongation(Gene),
+    SetRidges(Gene),
+    SetRidgeDepth(Gene),
+    SetStalkLength(Gene),
+    SetDroop(Gene),
+    SetColor(Gradient),
+    SetStalkColor(Color),
+    SetSkin(SkinPattern),
+}
+
+impl FruitMessage {
+    pub fn handle(self, fruit: &mut Fruit) {
+        match self {
+            Self::SetShape(x) => fruit.shape = x,
+            Self::SetSize(x) => fruit.size = x,
+            Self::SetElongation(x) => fruit.elongation = x,
+            Self::SetRidges(x) => fruit.ridges = x,
+            Self::SetRidgeDepth(x) => fruit.ridge_depth = x,
+            Self::SetStalkLength(x) => fruit.stalk_length = x,
+            Self::SetDroop(x) => fruit.droop = x,
+            Self::SetColor(x) => fruit.color = x,
+            Self::SetStalkColor(x) => fruit.stalk_color = x,
+            Self::SetSkin(x) => fruit.skin = x,
+        }
+    }
+}
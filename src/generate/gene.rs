@@ -0,0 +1,209 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use rand::Rng;
+use rhai::{Dynamic, Engine, Scope, AST, FLOAT};
+use serde::{Deserialize, Serialize};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::components::Slider;
+
+use super::GenerateContext;
+
+const MAX_CACHED: usize = 256;
+
+thread_local! {
+    static ENGINE: Engine = Engine::new();
+    static CACHE: RefCell<HashMap<String, Option<AST>>> = RefCell::new(HashMap::new());
+}
+
+fn compile(source: &str) -> Option<AST> {
+    CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+
+        if cache.len() >= MAX_CACHED && !cache.contains_key(source) {
+            cache.clear();
+        }
+
+        cache
+            .entry(source.to_string())
+            .or_insert_with(|| {
+                let source = source.replace('^', "**");
+                ENGINE.with(|engine| engine.compile_expression(source).ok())
+            })
+            .clone()
+    })
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Gene {
+    Constant(f32),
+    Expression(String),
+}
+
+impl Gene {
+    pub fn as_constant(&self) -> Option<f32> {
+        match self {
+            Self::Constant(x) => Some(*x),
+            Self::Expression(_) => None,
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::Constant(_) => true,
+            Self::Expression(source) => compile(source).is_some(),
+        }
+    }
+
+    pub fn eval(&self, ctx: &GenerateContext) -> f32 {
+        let source = match self {
+            Self::Constant(x) => return *x,
+            Self::Expression(source) => source,
+        };
+
+        let ast = match compile(source) {
+            Some(ast) => ast,
+            None => return 0.0,
+        };
+
+        let mut scope = Scope::new();
+        scope.push_constant("depth", ctx.depth as FLOAT);
+        scope.push_constant("index", ctx.index as FLOAT);
+        scope.push_constant("t", ctx.t as FLOAT);
+        scope.push_constant("parent_radius", ctx.parent_radius as FLOAT);
+
+        let result = ENGINE.with(|engine| engine.eval_ast_with_scope::<Dynamic>(&mut scope, &ast));
+
+        match result {
+            Ok(value) => value
+                .as_float()
+                .or_else(|_| value.as_int().map(|x| x as FLOAT))
+                .map(|x| x as f32)
+                .ok()
+                .filter(|x| x.is_finite())
+                .unwrap_or(0.0),
+            Err(_) => 0.0,
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, range: f32, variance: f32) {
+        match self {
+            Self::Constant(x) => *x += rng.gen_range(-range..range) * variance,
+            Self::Expression(source) => *source = perturb_literals(source, rng, variance),
+        }
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        if rng.gen() {
+            self.clone()
+        } else {
+            other.clone()
+        }
+    }
+
+    #[allow(clippy::unnecessary_operation)]
+    pub fn view(&self, name: &str, min: f32, max: f32, callback: Callback<Gene>) -> Html {
+        let toggle = match self {
+            Self::Constant(x) => Self::Expression(x.to_string()),
+            Self::Expression(_) => Self::Constant(self.eval(&GenerateContext::new())),
+        };
+
+        let onclick = callback.reform(move |_| toggle.clone());
+
+        let input = match self {
+            Self::Constant(x) => html! {
+                <Slider
+                    { min }
+                    { max }
+                    value={ *x }
+                    oninput={ callback.reform(Self::Constant) }
+                />
+            },
+            Self::Expression(source) => {
+                let oninput = callback.reform(|event: InputEvent| {
+                    Self::Expression(event.target_unchecked_into::<HtmlInputElement>().value())
+                });
+
+                let class = if self.is_valid() {
+                    "text-input"
+                } else {
+                    "text-input invalid"
+                };
+
+                html! {
+                    <input
+                        { class }
+                        type="text"
+                        value={ source.clone() }
+                        { oninput }
+                    />
+                }
+            }
+        };
+
+        html! {
+            <div class="property">
+                { name }
+                { input }
+                <button class="gene-toggle" { onclick }>{ "ƒ" }</button>
+            </div>
+        }
+    }
+}
+
+impl From<f32> for Gene {
+    fn from(x: f32) -> Self {
+        Self::Constant(x)
+    }
+}
+
+fn is_identifier(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.'
+}
+
+fn perturb_literals(source: &str, rng: &mut impl Rng, variance: f32) -> String {
+    let chars = source.chars().collect::<Vec<_>>();
+    let mut result = String::with_capacity(source.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let starts_literal = chars[i].is_ascii_digit() && (i == 0 || !is_identifier(chars[i - 1]));
+
+        if !starts_literal {
+            result.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        while i < chars.len() && chars[i].is_ascii_digit() {
+            i += 1;
+        }
+
+        if i + 1 < chars.len() && chars[i] == '.' && chars[i + 1].is_ascii_digit() {
+            i += 1;
+
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+        }
+
+        let literal = chars[start..i].iter().collect::<String>();
+        let factor = 1.0 + rng.gen_range(-0.5..0.5) * variance;
+
+        if literal.contains('.') {
+            match literal.parse::<f32>() {
+                Ok(x) => result.push_str(&format!("{:.3}", x * factor)),
+                Err(_) => result.push_str(&literal),
+            }
+        } else {
+            match literal.parse::<i64>() {
+                Ok(x) => result.push_str(&((x as f32 * factor).round() as i64).to_string()),
+                Err(_) => result.push_str(&literal),
+            }
+        }
+    }
+
+    result
+}
@@ -0,0 +1,211 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use glam::{Quat, Vec3};
+use rand::{prelude::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::mesh::{Mesh, Organ, Vertex};
+
+use super::{frame, Color, Gene, GenerateContext, Gradient, LeafPattern, Material};
+
+const MAX_BLADES: u32 = 256;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Grass {
+    pub blades: Gene,
+    pub length: Gene,
+    pub width: Gene,
+    pub taper: Gene,
+    pub curvature: Gene,
+    pub spread: Gene,
+    pub lean: Gene,
+    pub seed: u64,
+    pub color: Gradient,
+    pub pattern: LeafPattern,
+}
+
+impl Grass {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            blades: rng.gen_range(4.0..32.0).into(),
+            length: rng.gen_range(0.2..1.0).into(),
+            width: rng.gen_range(0.01..0.05).into(),
+            taper: rng.gen_range(0.5..1.0).into(),
+            curvature: rng.gen_range(0.0..1.5).into(),
+            spread: rng.gen_range(0.0..0.2).into(),
+            lean: rng.gen_range(0.0..0.8).into(),
+            seed: rng.gen(),
+            color: Gradient::near(rng, Color::rgb(0.8, 1.0, 0.7), 0.2),
+            pattern: LeafPattern::new(rng),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.blades.mutate(rng, 4.0, variance);
+        self.length.mutate(rng, 0.25, variance);
+        self.width.mutate(rng, 0.01, variance);
+        self.taper.mutate(rng, 0.25, variance);
+        self.curvature.mutate(rng, 0.5, variance);
+        self.spread.mutate(rng, 0.05, variance);
+        self.lean.mutate(rng, 0.25, variance);
+        self.color.mutate(rng, variance);
+        self.pattern.mutate(rng, variance);
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            blades: self.blades.crossover(&other.blades, rng),
+            length: self.length.crossover(&other.length, rng),
+            width: self.width.crossover(&other.width, rng),
+            taper: self.taper.crossover(&other.taper, rng),
+            curvature: self.curvature.crossover(&other.curvature, rng),
+            spread: self.spread.crossover(&other.spread, rng),
+            lean: self.lean.crossover(&other.lean, rng),
+            seed: if rng.gen() { self.seed } else { other.seed },
+            color: self.color.crossover(&other.color, rng),
+            pattern: if rng.gen() {
+                self.pattern.clone()
+            } else {
+                other.pattern.clone()
+            },
+        }
+    }
+
+    pub fn view(&self, callback: &Callback<GrassMessage>) -> Html {
+        html! {
+            <>
+                { self.blades.view("Blades", 1.0, 128.0, callback.reform(GrassMessage::SetBlades)) }
+                { self.length.view("Length", 0.05, 2.0, callback.reform(GrassMessage::SetLength)) }
+                { self.width.view("Width", 0.002, 0.1, callback.reform(GrassMessage::SetWidth)) }
+                { self.taper.view("Taper", 0.0, 1.0, callback.reform(GrassMessage::SetTaper)) }
+                { self.curvature.view("Curvature", 0.0, 3.0, callback.reform(GrassMessage::SetCurvature)) }
+                { self.spread.view("Spread", 0.0, 0.5, callback.reform(GrassMessage::SetSpread)) }
+                { self.lean.view("Lean", 0.0, FRAC_PI_2, callback.reform(GrassMessage::SetLean)) }
+                { self.color.view(&callback.reform(GrassMessage::SetColor)) }
+                { self.pattern.view(&callback.reform(GrassMessage::SetPattern)) }
+            </>
+        }
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let blades = self.blades.eval(&ctx).round().clamp(0.0, MAX_BLADES as f32) as u32;
+        let spread = self.spread.eval(&ctx) * ctx.scale;
+        let lean = self.lean.eval(&ctx);
+
+        let up = frame::orthogonalize(direction, up);
+        let steps = (ctx.tessellation.leaf_steps / 2).max(2);
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let first = mesh.vertices.len();
+
+        for i in 0..blades {
+            let ctx = GenerateContext {
+                index: i,
+                t: i as f32 / (blades.max(2) - 1) as f32,
+                ..ctx.child()
+            };
+
+            let length = self.length.eval(&ctx) * ctx.scale * rng.gen_range(0.6..1.0);
+            let width = self.width.eval(&ctx) * ctx.scale;
+            let taper = self.taper.eval(&ctx).clamp(0.0, 1.0);
+            let curvature = self.curvature.eval(&ctx) * rng.gen_range(0.5..1.0);
+
+            let angle = rng.gen_range(0.0..TAU);
+            let distance = rng.gen_range(0.0f32..1.0).sqrt();
+
+            let out = Quat::from_axis_angle(direction, angle) * up;
+            let root = start + out * distance * spread;
+
+            let tilt = Quat::from_axis_angle(direction.cross(out), -lean * distance);
+            let heading = tilt * direction;
+            let facing = Quat::from_axis_angle(heading, rng.gen_range(-0.5..0.5)) * out;
+            let right = heading.cross(facing).normalize();
+
+            let mut position = root;
+            let step = length / steps as f32;
+
+            for j in 0..=steps {
+                let x = j as f32 / steps as f32;
+                let half = width * (1.0 - taper * x) / 2.0;
+                let color = self.color.at(x).to_array();
+
+                let bend = Quat::from_axis_angle(right, -curvature * x * x);
+                let along = bend * heading;
+
+                if j > 0 {
+                    position += along * step;
+                }
+
+                mesh.vertices.push(Vertex {
+                    position: (position + right * half).into(),
+                    normal: [0.0; 3],
+                    uv: [1.0, x],
+                    color,
+                    ..Default::default()
+                });
+
+                mesh.vertices.push(Vertex {
+                    position: (position - right * half).into(),
+                    normal: [0.0; 3],
+                    uv: [0.0, x],
+                    color,
+                    ..Default::default()
+                });
+
+                if j > 0 {
+                    let l = mesh.vertices.len() as u32;
+
+                    mesh.indices.push(l - 1);
+                    mesh.indices.push(l - 2);
+                    mesh.indices.push(l - 3);
+
+                    mesh.indices.push(l - 2);
+                    mesh.indices.push(l - 4);
+                    mesh.indices.push(l - 3);
+                }
+            }
+        }
+
+        let vertices = first..mesh.vertices.len();
+        mesh.tag(vertices.clone(), Organ::Leaf);
+        ctx.apply_material(mesh, vertices, Material::Leaf(self.pattern.clone()));
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+pub enum GrassMessage {
+    SetBlades(Gene),
+    SetLength(Gene),
+    SetWidth(Gene),
+    SetTaper(Gene),
+    SetCurvature(Gene),
+    SetSpread(Gene),
+    SetLean(Gene),
+    SetColor(Gradient),
+    SetPattern(LeafPattern),
+}
+
+impl GrassMessage {
+    pub fn handle(self, grass: &mut Grass) {
+        match self {
+            Self::SetBlades(x) => grass.blades = x,
+            Self::SetLength(x) => grass.length = x,
+            Self::SetWidth(x) => grass.width = x,
+            Self::SetTaper(x) => grass.taper = x,
+            Self::SetCurvature(x) => grass.curvature = x,
+            Self::SetSpread(x) => grass.spread = x,
+            Self::SetLean(x) => grass.lean = x,
+            Self::SetColor(x) => grass.color = x,
+            Self::SetPattern(x) => grass.pattern = x,
+        }
+    }
+}
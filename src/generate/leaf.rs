@@ -1,130 +1,350 @@
-use glam::Vec3;
+use glam::{Quat, Vec3};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use yew::prelude::*;
 
 use crate::{
-    components::Slider,
-    mesh::{Mesh, Vertex},
+    mesh::{Mesh, Organ, Vertex},
     println,
 };
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct Leaf {
+use super::{
+    curve::{Curve, CurvePoint},
+    frame, tube, Color, Gene, GenerateContext, Gradient, LeafPattern, LeafShape, Material,
+};
+
+const STALK_POINTS: usize = 4;
+
+#[allow(clippy::too_many_arguments)]
+pub fn stalk(
+    mesh: &mut Mesh,
+    start: Vec3,
+    direction: Vec3,
+    up: Vec3,
     length: f32,
-    width: f32,
     bend: f32,
-    bend_profile: f32,
+    radius: f32,
+    taper: f32,
+    color: Color,
+    ctx: &GenerateContext,
+) -> (Curve, Vec<CurvePoint>) {
+    let axis = direction
+        .cross(frame::orthogonalize(direction, up))
+        .normalize();
+    let dt = 1.0 / STALK_POINTS as f32;
+
+    let mut position = start;
+    let mut control = vec![position];
+
+    for i in 0..STALK_POINTS {
+        let heading = Quat::from_axis_angle(axis, bend * (i as f32 + 0.5) * dt) * direction;
+
+        position += heading * length * dt;
+        control.push(position);
+    }
+
+    let curve = Curve::new(control, direction, up, 0.0);
+
+    if radius <= 0.0 {
+        return (curve, Vec::new());
+    }
+
+    let tessellation = ctx.tessellation;
+    let points = curve.adaptive(tessellation.ring_angle(radius), tessellation.curve_samples);
+
+    let first = mesh.vertices.len();
+
+    tube::tube(mesh, &points, tessellation.radial(radius), |point| {
+        radius * (1.0 - point.t * taper)
+    });
+
+    for vertex in mesh.vertices[first..].iter_mut() {
+        vertex.uv[0] = 0.5;
+        vertex.color = color.to_array();
+    }
+
+    (curve, points)
+}
+
+pub struct BladeRow {
+    pub center: Vec3,
+    pub right: Vec3,
+    pub normal: Vec3,
+    pub width: f32,
+    pub color: Color,
+}
+
+pub fn blade(
+    mesh: &mut Mesh,
+    steps: u32,
+    columns: u32,
+    fold: f32,
+    cupping: f32,
+    row: impl Fn(f32) -> BladeRow,
+) {
+    for i in 0..=steps {
+        let x = i as f32 / steps as f32;
+        let BladeRow {
+            center,
+            right,
+            normal,
+            width,
+            color,
+        } = row(x);
+
+        for j in 0..columns {
+            let u = 1.0 - j as f32 / (columns - 1) as f32;
+            let s = u * 2.0 - 1.0;
+
+            let across = s * width;
+            let lift = across.abs() * fold.sin() + cupping * s * s * width;
+
+            let p = center + right * across * fold.cos() + normal * lift;
+
+            mesh.vertices.push(Vertex {
+                position: p.into(),
+                normal: [0.0; 3],
+                uv: [u, x],
+                color: color.to_array(),
+                ..Default::default()
+            });
+        }
+
+        if i > 0 {
+            stitch_row(mesh, columns);
+        }
+    }
+}
+
+fn stitch_row(mesh: &mut Mesh, columns: u32) {
+    let row = mesh.vertices.len() as u32 - columns;
+    let previous = row - columns;
+
+    for j in 0..columns - 1 {
+        let a = row + j;
+        let b = row + j + 1;
+        let c = previous + j;
+        let d = previous + j + 1;
+
+        mesh.indices.push(b);
+        mesh.indices.push(a);
+        mesh.indices.push(d);
+
+        mesh.indices.push(a);
+        mesh.indices.push(c);
+        mesh.indices.push(d);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Leaf {
+    length: Gene,
+    width: Gene,
+    bend: Gene,
+    bend_profile: Gene,
+    cupping: Gene,
+    fold: Gene,
+    twist: Gene,
+    petiole_length: Gene,
+    petiole_radius: Gene,
+    petiole_bend: Gene,
+    pub shape: LeafShape,
+    pub color: Gradient,
+    pub pattern: LeafPattern,
 }
 
 impl Leaf {
     pub fn new(rng: &mut impl Rng) -> Self {
         Self {
-            length: rng.gen_range(0.1..1.0),
-            width: rng.gen_range(0.1..1.0),
-            bend: rng.gen_range(0.0..0.5),
-            bend_profile: rng.gen_range(0.5..5.0),
+            length: rng.gen_range(0.1..1.0).into(),
+            width: rng.gen_range(0.1..1.0).into(),
+            bend: rng.gen_range(0.0..0.5).into(),
+            bend_profile: rng.gen_range(0.5..5.0).into(),
+            cupping: rng.gen_range(-0.5..0.5).into(),
+            fold: rng.gen_range(0.0..0.5).into(),
+            twist: rng.gen_range(-0.5..0.5).into(),
+            petiole_length: rng.gen_range(0.0..0.3).into(),
+            petiole_radius: rng.gen_range(0.005..0.02).into(),
+            petiole_bend: rng.gen_range(-0.5..0.5).into(),
+            shape: LeafShape::new(rng),
+            color: Gradient::near(rng, Color::rgb(0.9, 1.0, 0.85), 0.2),
+            pattern: LeafPattern::new(rng),
         }
     }
 
     pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
-        self.length += rng.gen_range(-0.5..0.5) * variance;
-        self.width += rng.gen_range(-0.5..0.5) * variance;
-        self.bend += rng.gen_range(-0.25..0.25) * variance;
-        self.bend_profile += rng.gen_range(-0.25..0.25) * variance;
+        self.length.mutate(rng, 0.5, variance);
+        self.width.mutate(rng, 0.5, variance);
+        self.bend.mutate(rng, 0.25, variance);
+        self.bend_profile.mutate(rng, 0.25, variance);
+        self.cupping.mutate(rng, 0.25, variance);
+        self.fold.mutate(rng, 0.25, variance);
+        self.twist.mutate(rng, 0.25, variance);
+        self.petiole_length.mutate(rng, 0.1, variance);
+        self.petiole_radius.mutate(rng, 0.005, variance);
+        self.petiole_bend.mutate(rng, 0.25, variance);
+        self.shape.mutate(rng, variance);
+        self.color.mutate(rng, variance);
+        self.pattern.mutate(rng, variance);
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            length: self.length.crossover(&other.length, rng),
+            width: self.width.crossover(&other.width, rng),
+            bend: self.bend.crossover(&other.bend, rng),
+            bend_profile: self.bend_profile.crossover(&other.bend_profile, rng),
+            cupping: self.cupping.crossover(&other.cupping, rng),
+            fold: self.fold.crossover(&other.fold, rng),
+            twist: self.twist.crossover(&other.twist, rng),
+            petiole_length: self.petiole_length.crossover(&other.petiole_length, rng),
+            petiole_radius: self.petiole_radius.crossover(&other.petiole_radius, rng),
+            petiole_bend: self.petiole_bend.crossover(&other.petiole_bend, rng),
+            shape: if rng.gen() {
+                self.shape.clone()
+            } else {
+                other.shape.clone()
+            },
+            color: self.color.crossover(&other.color, rng),
+            pattern: if rng.gen() {
+                self.pattern.clone()
+            } else {
+                other.pattern.clone()
+            },
+        }
     }
 
     pub fn view(&self, callback: &Callback<LeafMessage>) -> Html {
         html! {
             <>
-                <div class="property">
-                    { "Length" }
-                    <Slider
-                        min=0.1
-                        max=1.0
-                        value={ self.length }
-                        oninput={ callback.reform(LeafMessage::SetLength) }
-                    />
-                </div>
-                <div class="property">
-                    { "Width" }
-                    <Slider
-                        min=0.1
-                        max=1.0
-                        value={ self.width }
-                        oninput={ callback.reform(LeafMessage::SetWidth) }
-                    />
-                </div>
-                <div class="property">
-                    { "Bend" }
-                    <Slider
-                        min=0.0
-                        max=0.5
-                        value={ self.bend }
-                        oninput={ callback.reform(LeafMessage::SetBend) }
-                    />
-                </div>
-                <div class="property">
-                    { "Bend Factor" }
-                    <Slider
-                        min=0.5
-                        max=5.0
-                        value={ self.bend_profile }
-                        oninput={ callback.reform(LeafMessage::SetBendProfile) }
-                    />
-                </div>
+                { self.length.view("Length", 0.1, 1.0, callback.reform(LeafMessage::SetLength)) }
+                { self.width.view("Width", 0.1, 1.0, callback.reform(LeafMessage::SetWidth)) }
+                { self.bend.view("Bend", 0.0, 0.5, callback.reform(LeafMessage::SetBend)) }
+                { self.bend_profile.view("Bend Factor", 0.5, 5.0, callback.reform(LeafMessage::SetBendProfile)) }
+                { self.cupping.view("Cupping", -1.0, 1.0, callback.reform(LeafMessage::SetCupping)) }
+                { self.fold.view("Fold", 0.0, 1.5, callback.reform(LeafMessage::SetFold)) }
+                { self.twist.view("Twist", -3.0, 3.0, callback.reform(LeafMessage::SetTwist)) }
+                { self.petiole_length.view("Petiole Length", 0.0, 0.5, callback.reform(LeafMessage::SetPetioleLength)) }
+                { self.petiole_radius.view("Petiole Radius", 0.0, 0.05, callback.reform(LeafMessage::SetPetioleRadius)) }
+                { self.petiole_bend.view("Petiole Bend", -1.5, 1.5, callback.reform(LeafMessage::SetPetioleBend)) }
+                { self.shape.view(&callback.reform(LeafMessage::SetShape)) }
+                { self.color.view(&callback.reform(LeafMessage::SetColor)) }
+                { self.pattern.view(&callback.reform(LeafMessage::SetPattern)) }
             </>
         }
     }
 
-    pub fn generate(&self, mesh: &mut Mesh, start: Vec3, direction: Vec3, up: Vec3) {
-        let steps = 5;
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let first = mesh.vertices.len();
+        let end = self.petiole(mesh, start, direction, up, &ctx);
 
-        let right = up.cross(direction).normalize();
-        let up = direction.cross(right).normalize();
+        let petiole = first..mesh.vertices.len();
+        mesh.tag(petiole.clone(), Organ::Branch);
+        ctx.apply_material(mesh, petiole, Material::Plain);
 
-        for i in 0..=steps {
-            let x = i as f32 / steps as f32;
+        let first = mesh.vertices.len();
 
-            let bend = x.powf(self.bend_profile) * self.bend;
+        match end {
+            Some(end) => self.blade(mesh, end.position, end.direction, end.up, &ctx),
+            None => self.blade(mesh, start, direction, up, &ctx),
+        }
 
-            let width = (x * std::f32::consts::PI).sin() * self.width / 4.0;
+        let vertices = first..mesh.vertices.len();
+        mesh.tag(vertices.clone(), Organ::Leaf);
+        ctx.apply_material(mesh, vertices, Material::Leaf(self.pattern.clone()));
+    }
 
-            let a = start + direction * x * self.length + right * width - up * bend;
-            let b = start + direction * x * self.length - right * width - up * bend;
+    fn petiole(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: &GenerateContext,
+    ) -> Option<CurvePoint> {
+        let length = self.petiole_length.eval(ctx) * ctx.scale;
+        let radius = self.petiole_radius.eval(ctx) * ctx.scale;
+        let bend = self.petiole_bend.eval(ctx);
 
-            mesh.vertices.push(Vertex {
-                position: a.into(),
-                normal: [0.0; 3],
-                uv: [0.0; 2],
-            });
+        if length <= 0.0 || radius <= 0.0 {
+            return None;
+        }
 
-            mesh.vertices.push(Vertex {
-                position: b.into(),
-                normal: [0.0; 3],
-                uv: [0.0; 2],
-            });
+        let (_, points) = stalk(
+            mesh,
+            start,
+            direction,
+            up,
+            length,
+            bend,
+            radius,
+            0.0,
+            self.color.base,
+            ctx,
+        );
+
+        points.last().copied()
+    }
+
+    fn blade(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: &GenerateContext,
+    ) {
+        let steps = ctx.tessellation.leaf_steps.max(self.shape.resolution());
+        let side = (ctx.tessellation.leaf_steps / 4).max(1);
+        let columns = side * 2 + 1;
 
-            let l = mesh.vertices.len() as u32;
+        let length = self.length.eval(ctx) * ctx.scale;
+        let width = self.width.eval(ctx) * ctx.scale;
+        let bend = self.bend.eval(ctx) * ctx.scale;
+        let bend_profile = self.bend_profile.eval(ctx);
+        let cupping = self.cupping.eval(ctx);
+        let fold = self.fold.eval(ctx);
+        let twist = self.twist.eval(ctx);
 
-            if i > 0 {
-                mesh.indices.push(l - 1);
-                mesh.indices.push(l - 2);
-                mesh.indices.push(l - 3);
+        let right = up.cross(direction).normalize();
+        let up = direction.cross(right).normalize();
 
-                mesh.indices.push(l - 2);
-                mesh.indices.push(l - 4);
-                mesh.indices.push(l - 3);
+        blade(mesh, steps, columns, fold, cupping, |x| {
+            let bend = x.powf(bend_profile) * bend;
+            let rotation = Quat::from_axis_angle(direction, twist * x);
+
+            BladeRow {
+                center: start + direction * x * length - up * bend,
+                right: rotation * right,
+                normal: rotation * up,
+                width: self.shape.width(x) * width / 4.0,
+                color: self.color.at(x),
             }
-        }
+        });
     }
 }
 
 pub enum LeafMessage {
-    SetLength(f32),
-    SetWidth(f32),
-    SetBend(f32),
-    SetBendProfile(f32),
+    SetLength(Gene),
+    SetWidth(Gene),
+    SetBend(Gene),
+    SetBendProfile(Gene),
+    SetCupping(Gene),
+    SetFold(Gene),
+    SetTwist(Gene),
+    SetPetioleLength(Gene),
+    SetPetioleRadius(Gene),
+    SetPetioleBend(Gene),
+    SetShape(LeafShape),
+    SetColor(Gradient),
+    SetPattern(LeafPattern),
 }
 
 impl LeafMessage {
@@ -134,6 +354,15 @@ impl LeafMessage {
             Self::SetWidth(x) => leaf.width = x,
             Self::SetBend(x) => leaf.bend = x,
             Self::SetBendProfile(x) => leaf.bend_profile = x,
+            Self::SetCupping(x) => leaf.cupping = x,
+            Self::SetFold(x) => leaf.fold = x,
+            Self::SetTwist(x) => leaf.twist = x,
+            Self::SetPetioleLength(x) => leaf.petiole_length = x,
+            Self::SetPetioleRadius(x) => leaf.petiole_radius = x,
+            Self::SetPetioleBend(x) => leaf.petiole_bend = x,
+            Self::SetShape(x) => leaf.shape = x,
+            Self::SetColor(x) => leaf.color = x,
+            Self::SetPattern(x) => leaf.pattern = x,
         }
     }
 }
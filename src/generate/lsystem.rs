@@ -0,0 +1,543 @@
+use std::{collections::HashMap, f32::consts::PI};
+
+use glam::Vec3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::{components::Slider, mesh::Mesh};
+
+use super::{
+    appendage::Appendages,
+    branch::{Branch, RadiusMode},
+    frame,
+    profile::Profile,
+    tube::Cap,
+    turtle::{self, Fork, Turn},
+    BarkPattern, Color, Gene, GenerateContext, Gradient, Leaf, LeafMessage, PlantDna,
+};
+
+const MAX_ITERATIONS: u32 = 8;
+
+const MAX_SYMBOLS: usize = 4096;
+
+const MAX_NESTING: u32 = 256;
+
+const PRESETS: [(&str, &str, &str, u32, f32, f32); 4] = [
+    (
+        "Fractal Plant",
+        "X",
+        "X=F+[[X]-X]-F[-FX]+X; F=FF",
+        4,
+        25.0,
+        0.04,
+    ),
+    ("Weed", "F", "F=F[+F]F[-F]F", 3, 25.7, 0.05),
+    ("Shrub", "F", "F=FF-[-F+F+F]+[+F-F-F]", 3, 22.5, 0.06),
+    (
+        "Bush",
+        "A",
+        "A=[&FL!A]/////'[&FL!A]///////'[&FL!A]; F=S/////F; S=FL",
+        4,
+        22.5,
+        0.1,
+    ),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Command {
+    Forward(u32),
+    Turn(f32, f32, f32),
+    Push,
+    Pop,
+    Leaf,
+}
+
+fn parse_rules(rules: &str) -> HashMap<char, String> {
+    rules
+        .split([';', '\n'])
+        .filter_map(|rule| {
+            let (symbol, replacement) = rule.split_once("->").or_else(|| rule.split_once('='))?;
+
+            let mut symbol = symbol.trim().chars();
+
+            match (symbol.next(), symbol.next()) {
+                (Some(symbol), None) => Some((
+                    symbol,
+                    replacement.chars().filter(|c| !c.is_whitespace()).collect(),
+                )),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn merge_turns(a: (f32, f32, f32), b: (f32, f32, f32)) -> Option<(f32, f32, f32)> {
+    let axis = |(yaw, pitch, roll): (f32, f32, f32)| match (yaw != 0.0, pitch != 0.0, roll != 0.0) {
+        (_, false, false) => Some(0),
+        (false, true, false) => Some(1),
+        (false, false, true) => Some(2),
+        _ => None,
+    };
+
+    (axis(a)? == axis(b)?).then_some((a.0 + b.0, a.1 + b.1, a.2 + b.2))
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LSystem {
+    pub axiom: String,
+    pub rules: String,
+    pub iterations: u32,
+    pub angle: Gene,
+    pub step: Gene,
+    pub radius: Gene,
+    pub thinning: Gene,
+    pub color: Gradient,
+    pub bark: BarkPattern,
+    pub leaf: Leaf,
+}
+
+impl LSystem {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        let mut lsystem = Self {
+            axiom: String::new(),
+            rules: String::new(),
+            iterations: 0,
+            angle: 0.0.into(),
+            step: 0.0.into(),
+            radius: rng.gen_range(0.005..0.02).into(),
+            thinning: rng.gen_range(0.6..0.9).into(),
+            color: Gradient::near(rng, Color::rgb(1.0, 0.95, 0.9), 0.2),
+            bark: BarkPattern::new(rng),
+            leaf: Leaf::new(rng),
+        };
+
+        lsystem.preset(rng.gen_range(0..PRESETS.len()));
+        lsystem
+    }
+
+    fn preset(&mut self, i: usize) {
+        let (_, axiom, rules, iterations, angle, step) = PRESETS[i];
+
+        self.axiom = axiom.to_string();
+        self.rules = rules.to_string();
+        self.iterations = iterations;
+        self.angle = angle.to_radians().into();
+        self.step = step.into();
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.angle.mutate(rng, 0.1, variance);
+        self.step.mutate(rng, 0.01, variance);
+        self.radius.mutate(rng, 0.005, variance);
+        self.thinning.mutate(rng, 0.1, variance);
+        self.color.mutate(rng, variance);
+        self.bark.mutate(rng, variance);
+        self.leaf.mutate(rng, variance);
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        let grammar = if rng.gen() { self } else { other };
+
+        Self {
+            axiom: grammar.axiom.clone(),
+            rules: grammar.rules.clone(),
+            iterations: grammar.iterations,
+            angle: self.angle.crossover(&other.angle, rng),
+            step: self.step.crossover(&other.step, rng),
+            radius: self.radius.crossover(&other.radius, rng),
+            thinning: self.thinning.crossover(&other.thinning, rng),
+            color: self.color.crossover(&other.color, rng),
+            bark: if rng.gen() {
+                self.bark.clone()
+            } else {
+                other.bark.clone()
+            },
+            leaf: self.leaf.crossover(&other.leaf, rng),
+        }
+    }
+
+    #[allow(clippy::unnecessary_operation)]
+    pub fn view(&self, callback: &Callback<LSystemMessage>) -> Html {
+        let presets = PRESETS.iter().enumerate().map(|(i, (name, ..))| {
+            let onclick = callback.reform(move |_| LSystemMessage::SetPreset(i));
+
+            html! {
+                <option { onclick }>{ name }</option>
+            }
+        });
+
+        let text = |name: &str, value: &str, f: fn(String) -> LSystemMessage| {
+            let oninput = callback.reform(move |event: InputEvent| {
+                f(event.target_unchecked_into::<HtmlInputElement>().value())
+            });
+
+            html! {
+                <div class="property">
+                    { name.to_string() }
+                    <input class="text-input" type="text" value={ value.to_string() } { oninput } />
+                </div>
+            }
+        };
+
+        html! {
+            <>
+                <div class="property">
+                    { "Preset" }
+                    <select>
+                        <option selected=true disabled=true>{ "Custom" }</option>
+                        { for presets }
+                    </select>
+                </div>
+                { text("Axiom", &self.axiom, LSystemMessage::SetAxiom) }
+                { text("Rules", &self.rules, LSystemMessage::SetRules) }
+                <div class="property">
+                    { "Iterations" }
+                    <Slider
+                        min=0.0
+                        max={ MAX_ITERATIONS as f32 }
+                        steps={ MAX_ITERATIONS as i32 }
+                        value={ self.iterations as f32 }
+                        oninput={ callback.reform(|x: f32| LSystemMessage::SetIterations(x.round() as u32)) }
+                    />
+                </div>
+                { self.angle.view("Angle", 0.0, PI, callback.reform(LSystemMessage::SetAngle)) }
+                { self.step.view("Step", 0.005, 0.5, callback.reform(LSystemMessage::SetStep)) }
+                { self.radius.view("Radius", 0.001, 0.1, callback.reform(LSystemMessage::SetRadius)) }
+                { self.thinning.view("Thinning", 0.1, 1.0, callback.reform(LSystemMessage::SetThinning)) }
+                { self.color.view(&callback.reform(LSystemMessage::SetColor)) }
+                { self.bark.view(&callback.reform(LSystemMessage::SetBark)) }
+                <div class="container">
+                    { self.leaf.view(&callback.reform(LSystemMessage::ChangeLeaf)) }
+                </div>
+                <div class="property">
+                    <button class="gene-toggle" onclick={ callback.reform(|_| LSystemMessage::Bake) }>
+                        { "Convert To Nodes" }
+                    </button>
+                </div>
+            </>
+        }
+    }
+
+    fn expand(&self) -> String {
+        let rules = parse_rules(&self.rules);
+        let mut symbols = self.axiom.clone();
+
+        for _ in 0..self.iterations.min(MAX_ITERATIONS) {
+            let next = symbols
+                .chars()
+                .map(|c| match rules.get(&c) {
+                    Some(replacement) => replacement.clone(),
+                    None => c.to_string(),
+                })
+                .collect::<String>();
+
+            if next.chars().count() > MAX_SYMBOLS {
+                break;
+            }
+
+            symbols = next;
+        }
+
+        symbols
+    }
+
+    fn commands(&self, angle: f32) -> Vec<Command> {
+        let mut commands: Vec<Command> = Vec::new();
+        let mut open = 0;
+
+        for c in self.expand().chars() {
+            let command = match c {
+                'F' | 'G' => Command::Forward(1),
+                '+' => Command::Turn(angle, 0.0, 0.0),
+                '-' => Command::Turn(-angle, 0.0, 0.0),
+                '&' => Command::Turn(0.0, angle, 0.0),
+                '^' => Command::Turn(0.0, -angle, 0.0),
+                '\\' => Command::Turn(0.0, 0.0, angle),
+                '/' => Command::Turn(0.0, 0.0, -angle),
+                '|' => Command::Turn(PI, 0.0, 0.0),
+                '[' => Command::Push,
+                ']' if open > 0 => Command::Pop,
+                'L' => Command::Leaf,
+                _ => continue,
+            };
+
+            match command {
+                Command::Push => open += 1,
+                Command::Pop => open -= 1,
+                _ => {}
+            }
+
+            let merged = match (commands.last(), command) {
+                (Some(Command::Forward(a)), Command::Forward(b)) => Some(Command::Forward(a + b)),
+                (Some(&Command::Turn(y0, p0, r0)), Command::Turn(y1, p1, r1)) => {
+                    merge_turns((y0, p0, r0), (y1, p1, r1)).map(|(y, p, r)| Command::Turn(y, p, r))
+                }
+                _ => None,
+            };
+
+            match merged {
+                Some(merged) => *commands.last_mut().unwrap() = merged,
+                None => commands.push(command),
+            }
+        }
+
+        commands.extend(std::iter::repeat_n(Command::Pop, open));
+        commands
+    }
+
+    fn max_depth(commands: &[Command]) -> u32 {
+        let mut depth = 0;
+        let mut max = 0;
+
+        for command in commands {
+            match command {
+                Command::Push => {
+                    depth += 1;
+                    max = max.max(depth);
+                }
+                Command::Pop => depth -= 1,
+                _ => {}
+            }
+        }
+
+        max
+    }
+
+    fn segment(
+        &self,
+        length: Gene,
+        radius: Gene,
+        depth: u32,
+        max_depth: u32,
+        end: PlantDna,
+    ) -> Branch {
+        let color = self.color.at(depth as f32 / max_depth.max(1) as f32);
+
+        Branch {
+            length,
+            radius,
+            radius_mode: RadiusMode::Free,
+            profile: Profile::Circle,
+            roughness: 0.0.into(),
+            cap: Cap::None,
+            weld: true,
+            bend: 0.0.into(),
+            taper: 1.0.into(),
+            gravitropism: 0.0.into(),
+            phototropism: 0.0.into(),
+            twist: 0.0.into(),
+            wiggle: 0.0.into(),
+            seed: 0,
+            color: Gradient {
+                base: color,
+                tip: color,
+            },
+            bark: self.bark.clone(),
+            appendages: Appendages::none(),
+            end: Box::new(end),
+        }
+    }
+
+    pub fn bake(&self, ctx: &GenerateContext) -> PlantDna {
+        let commands = self.commands(self.angle.eval(ctx));
+        let max_depth = Self::max_depth(&commands);
+
+        let mut i = 0;
+        self.bake_commands(&commands, &mut i, 0, max_depth, 0)
+    }
+
+    fn scaled(gene: &Gene, factor: f32) -> Gene {
+        match gene {
+            Gene::Constant(x) => Gene::Constant(x * factor),
+            Gene::Expression(source) => Gene::Expression(format!("({}) * {:?}", source, factor)),
+        }
+    }
+
+    fn baked_radius(&self, depth: u32) -> Gene {
+        let thinning = match &self.thinning {
+            Gene::Constant(thinning) => {
+                return Self::scaled(&self.radius, thinning.powi(depth as i32))
+            }
+            Gene::Expression(thinning) => thinning,
+        };
+
+        let radius = match &self.radius {
+            Gene::Constant(x) => format!("{:?}", x),
+            Gene::Expression(source) => source.clone(),
+        };
+
+        Gene::Expression(format!("({}) * ({})^{:?}", radius, thinning, depth as f32))
+    }
+
+    fn bake_commands(
+        &self,
+        commands: &[Command],
+        i: &mut usize,
+        depth: u32,
+        max_depth: u32,
+        nesting: u32,
+    ) -> PlantDna {
+        if nesting >= MAX_NESTING {
+            Self::skip_brackets(commands, i);
+            return PlantDna::None;
+        }
+
+        let command = match commands.get(*i) {
+            Some(command) => *command,
+            None => return PlantDna::None,
+        };
+
+        *i += 1;
+        let nesting = nesting + 1;
+
+        let fork = |first: PlantDna, second: PlantDna| match second {
+            PlantDna::None => first,
+            second => PlantDna::Fork(Fork {
+                first: Box::new(first),
+                second: Box::new(second),
+            }),
+        };
+
+        match command {
+            Command::Forward(n) => {
+                let length = Self::scaled(&self.step, n as f32);
+                let radius = self.baked_radius(depth);
+                let rest = self.bake_commands(commands, i, depth, max_depth, nesting);
+
+                PlantDna::Branch(self.segment(length, radius, depth, max_depth, rest))
+            }
+            Command::Turn(yaw, pitch, roll) => PlantDna::Turn(Turn {
+                yaw: yaw.into(),
+                pitch: pitch.into(),
+                roll: roll.into(),
+                value: Box::new(self.bake_commands(commands, i, depth, max_depth, nesting)),
+            }),
+            Command::Push => {
+                let inner = self.bake_commands(commands, i, depth + 1, max_depth, nesting);
+                let rest = self.bake_commands(commands, i, depth, max_depth, nesting);
+
+                fork(inner, rest)
+            }
+            Command::Pop => PlantDna::None,
+            Command::Leaf => {
+                let rest = self.bake_commands(commands, i, depth, max_depth, nesting);
+
+                fork(PlantDna::Leaf(self.leaf.clone()), rest)
+            }
+        }
+    }
+
+    fn skip_brackets(commands: &[Command], i: &mut usize) {
+        let mut open = 0;
+
+        while let Some(command) = commands.get(*i) {
+            *i += 1;
+
+            match command {
+                Command::Push => open += 1,
+                Command::Pop if open == 0 => return,
+                Command::Pop => open -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let step = self.step.eval(&ctx);
+        let commands = self.commands(self.angle.eval(&ctx));
+        let max_depth = Self::max_depth(&commands);
+
+        let mut turtle = (start, direction, frame::orthogonalize(direction, up), 0);
+        let mut stack = Vec::new();
+        let mut index = 0;
+
+        for command in commands {
+            let (position, direction, up, depth) = turtle;
+
+            let ctx = GenerateContext {
+                index,
+                ..ctx.child()
+            };
+
+            match command {
+                Command::Forward(n) => {
+                    let length = step * n as f32;
+                    let radius =
+                        self.radius.eval(&ctx) * self.thinning.eval(&ctx).powi(depth as i32);
+                    let segment = self.segment(
+                        length.into(),
+                        radius.into(),
+                        depth,
+                        max_depth,
+                        PlantDna::None,
+                    );
+
+                    segment.generate(mesh, position, direction, up, ctx);
+
+                    turtle.0 += direction * length * ctx.scale;
+                    index += 1;
+                }
+                Command::Turn(yaw, pitch, roll) => {
+                    let (direction, up) = turtle::turn(direction, up, yaw, pitch, roll);
+                    turtle = (position, direction, up, depth);
+                }
+                Command::Push => {
+                    stack.push(turtle);
+                    turtle.3 += 1;
+                }
+                Command::Pop => {
+                    if let Some(saved) = stack.pop() {
+                        turtle = saved;
+                    }
+                }
+                Command::Leaf => {
+                    self.leaf.generate(mesh, position, direction, up, ctx);
+                    index += 1;
+                }
+            }
+        }
+    }
+}
+
+pub enum LSystemMessage {
+    SetPreset(usize),
+    SetAxiom(String),
+    SetRules(String),
+    SetIterations(u32),
+    SetAngle(Gene),
+    SetStep(Gene),
+    SetRadius(Gene),
+    SetThinning(Gene),
+    SetColor(Gradient),
+    SetBark(BarkPattern),
+    ChangeLeaf(LeafMessage),
+    Bake,
+}
+
+impl LSystemMessage {
+    pub fn handle(self, lsystem: &mut LSystem) {
+        match self {
+            Self::SetPreset(x) => lsystem.preset(x),
+            Self::SetAxiom(x) => lsystem.axiom = x,
+            Self::SetRules(x) => lsystem.rules = x,
+            Self::SetIterations(x) => lsystem.iterations = x,
+            Self::SetAngle(x) => lsystem.angle = x,
+            Self::SetStep(x) => lsystem.step = x,
+            Self::SetRadius(x) => lsystem.radius = x,
+            Self::SetThinning(x) => lsystem.thinning = x,
+            Self::SetColor(x) => lsystem.color = x,
+            Self::SetBark(x) => lsystem.bark = x,
+            Self::ChangeLeaf(msg) => msg.handle(&mut lsystem.leaf),
+            Self::Bake => {}
+        }
+    }
+}
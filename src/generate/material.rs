@@ -0,0 +1,67 @@
+use std::ops::Range;
+
+use crate::{
+    mesh::Mesh,
+    texture::{
+        atlas::{Atlas, AtlasRegion},
+        Texture,
+    },
+};
+
+use super::{BarkPattern, LeafPattern, PetalPattern, PlantDna, SkinPattern};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Material {
+    Plain,
+    Bark(BarkPattern),
+    Leaf(LeafPattern),
+    Petal(PetalPattern),
+    Skin(SkinPattern),
+}
+
+impl Material {
+    pub fn texture(&self) -> Texture {
+        match self {
+            Self::Plain => Texture::white(),
+            Self::Bark(pattern) => pattern.texture(),
+            Self::Leaf(pattern) => pattern.texture(),
+            Self::Petal(pattern) => pattern.texture(),
+            Self::Skin(pattern) => pattern.texture(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Materials {
+    pub materials: Vec<Material>,
+    pub atlas: Atlas,
+}
+
+impl Materials {
+    pub fn new(dna: &PlantDna) -> Self {
+        let mut materials = vec![Material::Plain];
+        dna.materials(&mut materials);
+
+        let textures = materials.iter().map(Material::texture).collect::<Vec<_>>();
+
+        Self {
+            materials,
+            atlas: Atlas::pack(&textures),
+        }
+    }
+
+    pub fn region(&self, material: &Material) -> AtlasRegion {
+        self.materials
+            .iter()
+            .position(|other| other == material)
+            .map_or(AtlasRegion::FULL, |i| self.atlas.regions[i])
+    }
+
+    pub fn apply(&self, mesh: &mut Mesh, vertices: Range<usize>, material: &Material) {
+        let region = self.region(material).to_array();
+
+        for vertex in mesh.vertices[vertices].iter_mut() {
+            vertex.region = region;
+        }
+    }
+}
@@ -1,6 +1,28 @@
+mod appendage;
 mod branch;
+mod colonization;
+mod color;
+mod compound;
+mod context;
+mod curve;
 mod distribution;
+mod flower;
+mod frame;
+mod fruit;
+mod gene;
+mod grass;
 mod leaf;
+mod lsystem;
+mod material;
+mod outline;
+mod pattern;
+mod profile;
+mod reference;
+mod roots;
+mod tessellation;
+mod tube;
+mod turtle;
+mod vine;
 
 use glam::Vec3;
 use rand::Rng;
@@ -11,25 +33,68 @@ use crate::mesh::Mesh;
 
 use self::{
     branch::{Branch, BranchMessage},
+    colonization::{Colonization, ColonizationMessage},
+    compound::{CompoundLeaf, CompoundLeafMessage},
     distribution::{Distribution, DistributionMessage},
+    flower::{Flower, FlowerMessage},
+    fruit::{Fruit, FruitMessage},
+    grass::{Grass, GrassMessage},
     leaf::{Leaf, LeafMessage},
+    lsystem::{LSystem, LSystemMessage},
+    reference::{Named, NamedMessage, Ref, RefMessage},
+    roots::{Roots, RootsMessage},
+    turtle::{Fork, ForkMessage, Turn, TurnMessage},
+    vine::{Vine, VineMessage},
+};
+pub use self::{
+    color::{Color, Gradient},
+    context::{GenerateContext, Scope},
+    gene::Gene,
+    material::{Material, Materials},
+    outline::LeafShape,
+    pattern::{BarkPattern, LeafPattern, PetalPattern, SkinPattern},
+    tessellation::{Tessellation, TessellationSettings},
 };
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum PlantDna {
     Leaf(Leaf),
+    Compound(CompoundLeaf),
+    Flower(Flower),
+    Fruit(Fruit),
+    Roots(Roots),
+    Grass(Grass),
+    Colonization(Colonization),
+    LSystem(LSystem),
+    Turn(Turn),
+    Fork(Fork),
+    Vine(Vine),
     Branch(Branch),
     Distribution(Distribution),
+    Named(Named),
+    Ref(Ref),
     None,
 }
 
 impl PlantDna {
     pub fn new(rng: &mut impl Rng) -> Self {
-        match rng.gen_range(0u32..4) {
+        match rng.gen_range(0u32..16) {
             0 => Self::Leaf(Leaf::new(rng)),
             1 => Self::Branch(Branch::new(rng)),
             2 => Self::Distribution(Distribution::new(rng)),
             3 => Self::None,
+            4 => Self::Compound(CompoundLeaf::new(rng)),
+            5 => Self::Flower(Flower::new(rng)),
+            6 => Self::Fruit(Fruit::new(rng)),
+            7 => Self::Grass(Grass::new(rng)),
+            8 => Self::Named(Named::new(rng)),
+            9 => Self::Ref(Ref::new(rng)),
+            10 => Self::Roots(Roots::new(rng)),
+            11 => Self::Colonization(Colonization::new(rng)),
+            12 => Self::LSystem(LSystem::new(rng)),
+            13 => Self::Turn(Turn::new(rng)),
+            14 => Self::Fork(Fork::new(rng)),
+            15 => Self::Vine(Vine::new(rng)),
             _ => unreachable!(),
         }
     }
@@ -42,30 +107,111 @@ impl PlantDna {
 
         match self {
             Self::Leaf(leaf) => leaf.mutate(rng, variance),
+            Self::Compound(compound) => compound.mutate(rng, variance),
+            Self::Flower(flower) => flower.mutate(rng, variance),
             Self::Branch(branch) => branch.mutate(rng, variance),
             Self::Distribution(distribution) => distribution.mutate(rng, variance),
+            Self::Named(named) => named.mutate(rng, variance),
+            Self::Ref(reference) => reference.mutate(rng, variance),
+            Self::Fruit(fruit) => fruit.mutate(rng, variance),
+            Self::Roots(roots) => roots.mutate(rng, variance),
+            Self::Grass(grass) => grass.mutate(rng, variance),
+            Self::Colonization(colonization) => colonization.mutate(rng, variance),
+            Self::LSystem(lsystem) => lsystem.mutate(rng, variance),
+            Self::Turn(turn) => turn.mutate(rng, variance),
+            Self::Fork(fork) => fork.mutate(rng, variance),
+            Self::Vine(vine) => vine.mutate(rng, variance),
             Self::None => {}
         }
     }
 
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        match (self, other) {
+            (Self::Leaf(a), Self::Leaf(b)) => Self::Leaf(a.crossover(b, rng)),
+            (Self::Compound(a), Self::Compound(b)) => Self::Compound(a.crossover(b, rng)),
+            (Self::Flower(a), Self::Flower(b)) => Self::Flower(a.crossover(b, rng)),
+            (Self::Branch(a), Self::Branch(b)) => Self::Branch(a.crossover(b, rng)),
+            (Self::Distribution(a), Self::Distribution(b)) => {
+                Self::Distribution(a.crossover(b, rng))
+            }
+            (Self::Named(a), Self::Named(b)) => Self::Named(a.crossover(b, rng)),
+            (Self::Ref(a), Self::Ref(b)) => Self::Ref(a.crossover(b, rng)),
+            (Self::Fruit(a), Self::Fruit(b)) => Self::Fruit(a.crossover(b, rng)),
+            (Self::Roots(a), Self::Roots(b)) => Self::Roots(a.crossover(b, rng)),
+            (Self::Grass(a), Self::Grass(b)) => Self::Grass(a.crossover(b, rng)),
+            (Self::Colonization(a), Self::Colonization(b)) => {
+                Self::Colonization(a.crossover(b, rng))
+            }
+            (Self::LSystem(a), Self::LSystem(b)) => Self::LSystem(a.crossover(b, rng)),
+            (Self::Turn(a), Self::Turn(b)) => Self::Turn(a.crossover(b, rng)),
+            (Self::Fork(a), Self::Fork(b)) => Self::Fork(a.crossover(b, rng)),
+            (Self::Vine(a), Self::Vine(b)) => Self::Vine(a.crossover(b, rng)),
+            _ if rng.gen() => self.clone(),
+            _ => other.clone(),
+        }
+    }
+
     pub fn view(&self, callback: &Callback<PlantMessage>) -> Html {
         let variant_html = match self {
             Self::Leaf(leaf) => leaf.view(&callback.reform(PlantMessage::Leaf)),
+            Self::Compound(compound) => compound.view(&callback.reform(PlantMessage::Compound)),
+            Self::Flower(flower) => flower.view(&callback.reform(PlantMessage::Flower)),
             Self::Branch(branch) => branch.view(&callback.reform(PlantMessage::Branch)),
             Self::Distribution(distribution) => {
                 distribution.view(&callback.reform(PlantMessage::Distribution))
             }
+            Self::Named(named) => named.view(&callback.reform(PlantMessage::Named)),
+            Self::Ref(reference) => reference.view(&callback.reform(PlantMessage::Ref)),
+            Self::Fruit(fruit) => fruit.view(&callback.reform(PlantMessage::Fruit)),
+            Self::Roots(roots) => roots.view(&callback.reform(PlantMessage::Roots)),
+            Self::Grass(grass) => grass.view(&callback.reform(PlantMessage::Grass)),
+            Self::Colonization(colonization) => {
+                colonization.view(&callback.reform(PlantMessage::Colonization))
+            }
+            Self::LSystem(lsystem) => lsystem.view(&callback.reform(PlantMessage::LSystem)),
+            Self::Turn(turn) => turn.view(&callback.reform(PlantMessage::Turn)),
+            Self::Fork(fork) => fork.view(&callback.reform(PlantMessage::Fork)),
+            Self::Vine(vine) => vine.view(&callback.reform(PlantMessage::Vine)),
             Self::None => html!(),
         };
 
         let variant_name = match self {
             Self::Leaf(_) => "Leaf",
+            Self::Compound(_) => "Compound",
+            Self::Flower(_) => "Flower",
             Self::Branch(_) => "Branch",
             Self::Distribution(_) => "Distribution",
+            Self::Named(_) => "Named",
+            Self::Ref(_) => "Ref",
+            Self::Fruit(_) => "Fruit",
+            Self::Roots(_) => "Roots",
+            Self::Grass(_) => "Grass",
+            Self::Colonization(_) => "Colonization",
+            Self::LSystem(_) => "L-System",
+            Self::Turn(_) => "Turn",
+            Self::Fork(_) => "Fork",
+            Self::Vine(_) => "Vine",
             Self::None => "None",
         };
 
-        let opts = ["Leaf", "Branch", "Distribution", "None"];
+        let opts = [
+            "Leaf",
+            "Compound",
+            "Flower",
+            "Fruit",
+            "Branch",
+            "Distribution",
+            "Named",
+            "Ref",
+            "Roots",
+            "Grass",
+            "Colonization",
+            "L-System",
+            "Turn",
+            "Fork",
+            "Vine",
+            "None",
+        ];
 
         let opts = opts.into_iter().map(|opt| {
             let onclick = callback.reform(move |_| PlantMessage::Base(String::from(opt)));
@@ -85,11 +231,81 @@ impl PlantDna {
         }
     }
 
-    pub fn generate_mesh(&self, mesh: &mut Mesh, start: Vec3, direction: Vec3, up: Vec3) {
+    pub fn materials(&self, materials: &mut Vec<Material>) {
+        let mut add = |material| {
+            if !materials.contains(&material) {
+                materials.push(material);
+            }
+        };
+
+        match self {
+            Self::Leaf(leaf) => add(Material::Leaf(leaf.pattern.clone())),
+            Self::Compound(compound) => add(Material::Leaf(compound.leaflet.pattern.clone())),
+            Self::Flower(flower) => {
+                add(Material::Petal(flower.pattern.clone()));
+                add(Material::Leaf(flower.sepal_pattern.clone()));
+            }
+            Self::Branch(branch) => {
+                add(Material::Bark(branch.bark.clone()));
+                branch.end.materials(materials);
+            }
+            Self::Distribution(distribution) => distribution.value.materials(materials),
+            Self::Named(named) => named.value.materials(materials),
+            Self::Roots(roots) => {
+                add(Material::Bark(roots.bark.clone()));
+                roots.shoot.materials(materials);
+            }
+            Self::Fruit(fruit) => add(Material::Skin(fruit.skin.clone())),
+            Self::Grass(grass) => add(Material::Leaf(grass.pattern.clone())),
+            Self::Colonization(colonization) => {
+                add(Material::Bark(colonization.bark.clone()));
+                colonization.end.materials(materials);
+            }
+            Self::LSystem(lsystem) => {
+                add(Material::Bark(lsystem.bark.clone()));
+                add(Material::Leaf(lsystem.leaf.pattern.clone()));
+            }
+            Self::Turn(turn) => turn.value.materials(materials),
+            Self::Fork(fork) => {
+                fork.first.materials(materials);
+                fork.second.materials(materials);
+            }
+            Self::Vine(vine) => {
+                add(Material::Bark(vine.bark.clone()));
+                vine.leaf.materials(materials);
+            }
+            Self::Ref(_) | Self::None => {}
+        }
+    }
+
+    pub fn generate_mesh(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
         match self {
-            Self::Leaf(leaf) => leaf.generate(mesh, start, direction, up),
-            Self::Branch(branch) => branch.generate(mesh, start, direction, up),
-            Self::Distribution(distribution) => distribution.generate(mesh, start, direction, up),
+            Self::Leaf(leaf) => leaf.generate(mesh, start, direction, up, ctx),
+            Self::Compound(compound) => compound.generate(mesh, start, direction, up, ctx),
+            Self::Flower(flower) => flower.generate(mesh, start, direction, up, ctx),
+            Self::Branch(branch) => branch.generate(mesh, start, direction, up, ctx),
+            Self::Distribution(distribution) => {
+                distribution.generate(mesh, start, direction, up, ctx)
+            }
+            Self::Named(named) => named.generate(mesh, start, direction, up, ctx),
+            Self::Ref(reference) => reference.generate(mesh, start, direction, up, ctx),
+            Self::Fruit(fruit) => fruit.generate(mesh, start, direction, up, ctx),
+            Self::Roots(roots) => roots.generate(mesh, start, direction, up, ctx),
+            Self::Grass(grass) => grass.generate(mesh, start, direction, up, ctx),
+            Self::Colonization(colonization) => {
+                colonization.generate(mesh, start, direction, up, ctx)
+            }
+            Self::LSystem(lsystem) => lsystem.generate(mesh, start, direction, up, ctx),
+            Self::Turn(turn) => turn.generate(mesh, start, direction, up, ctx),
+            Self::Fork(fork) => fork.generate(mesh, start, direction, up, ctx),
+            Self::Vine(vine) => vine.generate(mesh, start, direction, up, ctx),
             Self::None => {}
         }
     }
@@ -98,8 +314,20 @@ impl PlantDna {
 pub enum PlantMessage {
     Base(String),
     Leaf(LeafMessage),
+    Compound(CompoundLeafMessage),
+    Flower(FlowerMessage),
     Branch(BranchMessage),
     Distribution(DistributionMessage),
+    Named(NamedMessage),
+    Ref(RefMessage),
+    Fruit(FruitMessage),
+    Roots(RootsMessage),
+    Grass(GrassMessage),
+    Colonization(ColonizationMessage),
+    LSystem(LSystemMessage),
+    Turn(TurnMessage),
+    Fork(ForkMessage),
+    Vine(VineMessage),
 }
 
 impl PlantMessage {
@@ -107,16 +335,47 @@ impl PlantMessage {
         match (self, plant) {
             (Self::Base(base), plant) => match base.as_str() {
                 "Leaf" => *plant = PlantDna::Leaf(Leaf::new(rng)),
+                "Compound" => *plant = PlantDna::Compound(CompoundLeaf::new(rng)),
+                "Flower" => *plant = PlantDna::Flower(Flower::new(rng)),
                 "Branch" => *plant = PlantDna::Branch(Branch::new(rng)),
                 "Distribution" => *plant = PlantDna::Distribution(Distribution::new(rng)),
+                "Named" => *plant = PlantDna::Named(Named::new(rng)),
+                "Ref" => *plant = PlantDna::Ref(Ref::new(rng)),
+                "Fruit" => *plant = PlantDna::Fruit(Fruit::new(rng)),
+                "Roots" => *plant = PlantDna::Roots(Roots::new(rng)),
+                "Grass" => *plant = PlantDna::Grass(Grass::new(rng)),
+                "Colonization" => *plant = PlantDna::Colonization(Colonization::new(rng)),
+                "L-System" => *plant = PlantDna::LSystem(LSystem::new(rng)),
+                "Turn" => *plant = PlantDna::Turn(Turn::new(rng)),
+                "Fork" => *plant = PlantDna::Fork(Fork::new(rng)),
+                "Vine" => *plant = PlantDna::Vine(Vine::new(rng)),
                 "None" => *plant = PlantDna::None,
                 _ => panic!("invalid base type"),
             },
             (Self::Leaf(msg), PlantDna::Leaf(leaf)) => msg.handle(leaf),
+            (Self::Compound(msg), PlantDna::Compound(compound)) => msg.handle(compound),
+            (Self::Flower(msg), PlantDna::Flower(flower)) => msg.handle(flower),
             (Self::Branch(msg), PlantDna::Branch(branch)) => msg.handle(rng, branch),
             (Self::Distribution(msg), PlantDna::Distribution(distribution)) => {
                 msg.handle(rng, distribution)
             }
+            (Self::Named(msg), PlantDna::Named(named)) => msg.handle(rng, named),
+            (Self::Ref(msg), PlantDna::Ref(reference)) => msg.handle(reference),
+            (Self::Fruit(msg), PlantDna::Fruit(fruit)) => msg.handle(fruit),
+            (Self::Roots(msg), PlantDna::Roots(roots)) => msg.handle(rng, roots),
+            (Self::Grass(msg), PlantDna::Grass(grass)) => msg.handle(grass),
+            (Self::Colonization(msg), PlantDna::Colonization(colonization)) => {
+                msg.handle(rng, colonization)
+            }
+            (Self::LSystem(LSystemMessage::Bake), plant) => {
+                if let PlantDna::LSystem(lsystem) = plant {
+                    *plant = lsystem.bake(&GenerateContext::new());
+                }
+            }
+            (Self::LSystem(msg), PlantDna::LSystem(lsystem)) => msg.handle(lsystem),
+            (Self::Turn(msg), PlantDna::Turn(turn)) => msg.handle(rng, turn),
+            (Self::Fork(msg), PlantDna::Fork(fork)) => msg.handle(rng, fork),
+            (Self::Vine(msg), PlantDna::Vine(vine)) => msg.handle(rng, vine),
             _ => panic!("invalid message"),
         }
     }
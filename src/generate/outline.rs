@@ -0,0 +1,246 @@
+use std::f32::consts::PI;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::components::Slider;
+
+pub const CUSTOM_POINTS: usize = 8;
+
+const MAX_RESOLUTION: u32 = 256;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LeafShape {
+    Lanceolate,
+    Ovate,
+    Cordate,
+    Linear,
+    Lobed { lobes: f32, depth: f32 },
+    Palmate { lobes: f32, depth: f32 },
+    Serrated { teeth: f32, depth: f32 },
+    Custom(Vec<f32>),
+}
+
+impl LeafShape {
+    pub const NAMES: [&'static str; 8] = [
+        "Lanceolate",
+        "Ovate",
+        "Cordate",
+        "Linear",
+        "Lobed",
+        "Palmate",
+        "Serrated",
+        "Custom",
+    ];
+
+    pub fn new(rng: &mut impl Rng) -> Self {
+        let mut shape = Self::from_name(Self::NAMES[rng.gen_range(0..Self::NAMES.len() - 1)]);
+        shape.perturb(rng, 1.0);
+        shape
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "Lanceolate" => Self::Lanceolate,
+            "Ovate" => Self::Ovate,
+            "Cordate" => Self::Cordate,
+            "Linear" => Self::Linear,
+            "Lobed" => Self::Lobed {
+                lobes: 4.0,
+                depth: 0.4,
+            },
+            "Palmate" => Self::Palmate {
+                lobes: 5.0,
+                depth: 0.5,
+            },
+            "Serrated" => Self::Serrated {
+                teeth: 16.0,
+                depth: 0.1,
+            },
+            "Custom" => Self::Custom(
+                (0..CUSTOM_POINTS)
+                    .map(|i| {
+                        let x = (i + 1) as f32 / (CUSTOM_POINTS + 1) as f32;
+                        Self::Lanceolate.width(x)
+                    })
+                    .collect(),
+            ),
+            _ => panic!("invalid leaf shape"),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Lanceolate => "Lanceolate",
+            Self::Ovate => "Ovate",
+            Self::Cordate => "Cordate",
+            Self::Linear => "Linear",
+            Self::Lobed { .. } => "Lobed",
+            Self::Palmate { .. } => "Palmate",
+            Self::Serrated { .. } => "Serrated",
+            Self::Custom(_) => "Custom",
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        if rng.gen_range(0.0..1.0) < 0.1 * variance {
+            *self = Self::new(rng);
+        } else {
+            self.perturb(rng, variance);
+        }
+    }
+
+    fn perturb(&mut self, rng: &mut impl Rng, variance: f32) {
+        match self {
+            Self::Lobed { lobes, depth } | Self::Palmate { lobes, depth } => {
+                *lobes = (*lobes + rng.gen_range(-1.0..1.0) * variance).max(1.0);
+                *depth = (*depth + rng.gen_range(-0.2..0.2) * variance).clamp(0.0, 1.0);
+            }
+            Self::Serrated { teeth, depth } => {
+                *teeth = (*teeth + rng.gen_range(-4.0..4.0) * variance).max(1.0);
+                *depth = (*depth + rng.gen_range(-0.1..0.1) * variance).clamp(0.0, 1.0);
+            }
+            Self::Custom(points) => {
+                for point in points.iter_mut() {
+                    *point = (*point + rng.gen_range(-0.2..0.2) * variance).clamp(0.0, 1.0);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn resolution(&self) -> u32 {
+        let resolution = match self {
+            Self::Lobed { lobes, .. } | Self::Palmate { lobes, .. } => (*lobes * 8.0) as u32,
+            Self::Serrated { teeth, .. } => (*teeth * 4.0) as u32,
+            Self::Custom(points) => points.len() as u32 * 2,
+            _ => 0,
+        };
+
+        resolution.min(MAX_RESOLUTION)
+    }
+
+    pub fn width(&self, x: f32) -> f32 {
+        let x = x.clamp(0.0, 1.0);
+
+        match self {
+            Self::Lanceolate => (x * PI).sin(),
+            Self::Ovate => (x.powf(0.7) * PI).sin(),
+            Self::Cordate => (x.powf(0.6) * PI).sin() + 0.4 * (1.0 - x).powi(6),
+            Self::Linear => 1.0 - (x * 2.0 - 1.0).powi(8),
+            Self::Lobed { lobes, depth } => {
+                let lobe = (x * lobes.round().max(1.0) * PI).sin().abs();
+                (x * PI).sin() * (1.0 - depth + depth * lobe)
+            }
+            Self::Palmate { lobes, depth } => {
+                let lobe = (x * lobes.round().max(1.0) * PI).sin().abs();
+                (x.sqrt() * PI).sin() * (1.0 - depth + depth * lobe)
+            }
+            Self::Serrated { teeth, depth } => {
+                let tooth = (x * teeth.round().max(1.0)).fract();
+                (x * PI).sin() * (1.0 - depth * tooth)
+            }
+            Self::Custom(points) => custom_width(points, x),
+        }
+    }
+
+    #[allow(clippy::unnecessary_operation)]
+    pub fn view(&self, callback: &Callback<LeafShape>) -> Html {
+        let opts = Self::NAMES.into_iter().map(|name| {
+            let onclick = callback.reform(move |_| Self::from_name(name));
+
+            html! {
+                <option { onclick } selected={ name == self.name() }>{ name }</option>
+            }
+        });
+
+        let slider = |name: &str, min: f32, max: f32, value: f32, f: Box<dyn Fn(f32) -> Self>| {
+            html! {
+                <div class="property">
+                    { name.to_string() }
+                    <Slider { min } { max } { value } oninput={ callback.reform(f) } />
+                </div>
+            }
+        };
+
+        let properties = match self.clone() {
+            Self::Lobed { lobes, depth } => html! {
+                <>
+                    { slider("Lobes", 1.0, 8.0, lobes, Box::new(move |lobes| Self::Lobed { lobes, depth })) }
+                    { slider("Lobe Depth", 0.0, 1.0, depth, Box::new(move |depth| Self::Lobed { lobes, depth })) }
+                </>
+            },
+            Self::Palmate { lobes, depth } => html! {
+                <>
+                    { slider("Lobes", 1.0, 9.0, lobes, Box::new(move |lobes| Self::Palmate { lobes, depth })) }
+                    { slider("Lobe Depth", 0.0, 1.0, depth, Box::new(move |depth| Self::Palmate { lobes, depth })) }
+                </>
+            },
+            Self::Serrated { teeth, depth } => html! {
+                <>
+                    { slider("Teeth", 1.0, 32.0, teeth, Box::new(move |teeth| Self::Serrated { teeth, depth })) }
+                    { slider("Tooth Depth", 0.0, 0.5, depth, Box::new(move |depth| Self::Serrated { teeth, depth })) }
+                </>
+            },
+            Self::Custom(points) => {
+                let sliders = points.iter().enumerate().map(|(i, &value)| {
+                    let points = points.clone();
+
+                    slider(
+                        &format!("Width {}", i + 1),
+                        0.0,
+                        1.0,
+                        value,
+                        Box::new(move |x| {
+                            let mut points = points.clone();
+                            points[i] = x;
+                            Self::Custom(points)
+                        }),
+                    )
+                });
+
+                html!({ for sliders })
+            }
+            _ => html!(),
+        };
+
+        html! {
+            <>
+                <div class="property">
+                    { "Shape" }
+                    <select>{ for opts }</select>
+                </div>
+                { properties }
+            </>
+        }
+    }
+}
+
+fn custom_width(points: &[f32], x: f32) -> f32 {
+    let point = |i: isize| {
+        if i <= 0 || i as usize > points.len() {
+            0.0
+        } else {
+            points[i as usize - 1]
+        }
+    };
+
+    let segments = points.len() + 1;
+    let s = x * segments as f32;
+    let i = (s.floor() as isize).min(segments as isize - 1);
+    let t = s - i as f32;
+
+    let p0 = point(i - 1);
+    let p1 = point(i);
+    let p2 = point(i + 1);
+    let p3 = point(i + 2);
+
+    let width = 0.5
+        * (2.0 * p1
+            + (p2 - p0) * t
+            + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+            + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t * t * t);
+
+    width.max(0.0)
+}
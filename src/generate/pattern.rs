@@ -0,0 +1,344 @@
+use gloo_timers::callback::Timeout;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::{
+    components::{Slider, TextureUpload},
+    texture::{
+        encoding,
+        procedural::{self, Veins},
+        SharedTexture, Texture,
+    },
+};
+
+use super::Color;
+
+pub const TEXTURE_SIZE: u32 = 128;
+
+#[allow(clippy::unnecessary_operation)]
+fn slider<T: Clone + 'static>(
+    name: &str,
+    min: f32,
+    max: f32,
+    value: f32,
+    pattern: &T,
+    callback: &Callback<T>,
+    set: fn(&mut T, f32),
+) -> Html {
+    let pattern = pattern.clone();
+    let oninput = callback.reform(move |x| {
+        let mut pattern = pattern.clone();
+        set(&mut pattern, x);
+        pattern
+    });
+
+    html! {
+        <div class="property">
+            { name }
+            <Slider { min } { max } { value } { oninput } />
+        </div>
+    }
+}
+
+fn color<T: Clone + 'static>(
+    name: &str,
+    value: &Color,
+    pattern: &T,
+    callback: &Callback<T>,
+    set: fn(&mut T, Color),
+) -> Html {
+    let pattern = pattern.clone();
+    let callback = callback.reform(move |x| {
+        let mut pattern = pattern.clone();
+        set(&mut pattern, x);
+        pattern
+    });
+
+    value.view(name, callback)
+}
+
+#[allow(clippy::unnecessary_operation)]
+fn photo<T: Clone + 'static>(
+    file_name: &'static str,
+    pattern: &T,
+    callback: &Callback<T>,
+    get: fn(&T) -> &Option<SharedTexture>,
+    set: fn(&mut T, Option<SharedTexture>),
+    texture: fn(&T) -> Texture,
+) -> Html {
+    let upload = {
+        let pattern = pattern.clone();
+        callback.reform(move |photo| {
+            let mut pattern = pattern.clone();
+            set(&mut pattern, Some(photo));
+            pattern
+        })
+    };
+
+    let clear = {
+        let pattern = pattern.clone();
+        callback.reform(move |_| {
+            let mut pattern = pattern.clone();
+            set(&mut pattern, None);
+            pattern
+        })
+    };
+
+    let download = {
+        let pattern = pattern.clone();
+        Callback::from(move |event: MouseEvent| {
+            let anchor = event.target_unchecked_into::<web_sys::HtmlAnchorElement>();
+
+            if let Some(url) = encoding::png_url(&texture(&pattern)) {
+                anchor.set_href(&url);
+
+                Timeout::new(1_000, move || {
+                    let _ = web_sys::Url::revoke_object_url(&url);
+                })
+                .forget();
+            }
+        })
+    };
+
+    let clear = match get(pattern) {
+        Some(_) => html! {
+            <button class="gene-toggle" onclick={ clear }>{ "✕" }</button>
+        },
+        None => html!(),
+    };
+
+    html! {
+        <>
+            <div class="property">
+                { "Photo" }
+                <TextureUpload onupload={ upload } />
+                { clear }
+            </div>
+            <div class="property">
+                <a class="download" href="#" download={ file_name } onclick={ download }>
+                    { "Download Texture" }
+                </a>
+            </div>
+        </>
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BarkPattern {
+    pub base: Color,
+    #[serde(skip)]
+    pub photo: Option<SharedTexture>,
+    pub crack: Color,
+    pub ridges: f32,
+    pub roughness: f32,
+    pub seed: u32,
+}
+
+impl BarkPattern {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            base: Color::near(rng, Color::rgb(0.45, 0.35, 0.25), 0.3),
+            photo: None,
+            crack: Color::near(rng, Color::rgb(0.15, 0.1, 0.08), 0.2),
+            ridges: rng.gen_range(2.0..12.0),
+            roughness: rng.gen_range(0.0..1.0),
+            seed: rng.gen(),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.base.mutate(rng, variance);
+        self.crack.mutate(rng, variance);
+        self.ridges += rng.gen_range(-2.0..2.0) * variance;
+        self.ridges = self.ridges.clamp(1.0, 16.0);
+        self.roughness += rng.gen_range(-0.25..0.25) * variance;
+        self.roughness = self.roughness.clamp(0.0, 2.0);
+    }
+
+    pub fn view(&self, callback: &Callback<BarkPattern>) -> Html {
+        html! {
+            <>
+                { color("Bark", &self.base, self, callback, |p, x| p.base = x) }
+                { color("Cracks", &self.crack, self, callback, |p, x| p.crack = x) }
+                { slider("Ridges", 1.0, 16.0, self.ridges, self, callback, |p, x| p.ridges = x) }
+                { slider("Roughness", 0.0, 2.0, self.roughness, self, callback, |p, x| p.roughness = x) }
+                { photo("bark.png", self, callback, |p| &p.photo, |p, x| p.photo = x, Self::texture) }
+            </>
+        }
+    }
+
+    pub fn texture(&self) -> Texture {
+        procedural::bark(
+            TEXTURE_SIZE,
+            self.base.vec3(),
+            self.photo.as_deref(),
+            self.crack.vec3(),
+            self.ridges,
+            self.roughness,
+            self.seed,
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LeafPattern {
+    pub base: Color,
+    #[serde(skip)]
+    pub photo: Option<SharedTexture>,
+    pub tip: Color,
+    pub vein: Color,
+    pub veins: f32,
+    pub vein_angle: f32,
+    pub seed: u32,
+}
+
+impl LeafPattern {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        let base = Color::near(rng, Color::rgb(0.2, 0.5, 0.15), 0.3);
+
+        Self {
+            base,
+            photo: None,
+            tip: Color::near(rng, base, 0.3),
+            vein: Color::near(rng, Color::rgb(0.6, 0.75, 0.4), 0.3),
+            veins: rng.gen_range(3.0..12.0),
+            vein_angle: rng.gen_range(0.0..1.0),
+            seed: rng.gen(),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.base.mutate(rng, variance);
+        self.tip.mutate(rng, variance);
+        self.vein.mutate(rng, variance);
+        self.veins += rng.gen_range(-2.0..2.0) * variance;
+        self.veins = self.veins.clamp(1.0, 16.0);
+        self.vein_angle += rng.gen_range(-0.25..0.25) * variance;
+        self.vein_angle = self.vein_angle.clamp(0.0, 1.0);
+    }
+
+    pub fn view(&self, callback: &Callback<LeafPattern>) -> Html {
+        html! {
+            <>
+                { color("Base", &self.base, self, callback, |p, x| p.base = x) }
+                { color("Tip", &self.tip, self, callback, |p, x| p.tip = x) }
+                { color("Veins", &self.vein, self, callback, |p, x| p.vein = x) }
+                { slider("Vein Count", 1.0, 16.0, self.veins, self, callback, |p, x| p.veins = x) }
+                { slider("Vein Angle", 0.0, 1.0, self.vein_angle, self, callback, |p, x| p.vein_angle = x) }
+                { photo("leaf.png", self, callback, |p| &p.photo, |p, x| p.photo = x, Self::texture) }
+            </>
+        }
+    }
+
+    pub fn texture(&self) -> Texture {
+        procedural::leaf(
+            TEXTURE_SIZE,
+            self.base.vec3(),
+            self.photo.as_deref(),
+            self.tip.vec3(),
+            Veins {
+                color: self.vein.vec3(),
+                count: self.veins,
+                angle: self.vein_angle,
+            },
+            self.seed,
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PetalPattern {
+    pub center: Color,
+    pub edge: Color,
+    pub streaks: f32,
+    pub seed: u32,
+}
+
+impl PetalPattern {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        let edge = Color::rgb(rng.gen(), rng.gen(), rng.gen());
+
+        Self {
+            center: Color::near(rng, edge, 0.5),
+            edge,
+            streaks: rng.gen_range(0.0..1.0),
+            seed: rng.gen(),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.center.mutate(rng, variance);
+        self.edge.mutate(rng, variance);
+        self.streaks += rng.gen_range(-0.25..0.25) * variance;
+        self.streaks = self.streaks.clamp(0.0, 1.0);
+    }
+
+    pub fn view(&self, callback: &Callback<PetalPattern>) -> Html {
+        html! {
+            <>
+                { color("Petal Center", &self.center, self, callback, |p, x| p.center = x) }
+                { color("Petal Edge", &self.edge, self, callback, |p, x| p.edge = x) }
+                { slider("Streaks", 0.0, 1.0, self.streaks, self, callback, |p, x| p.streaks = x) }
+            </>
+        }
+    }
+
+    pub fn texture(&self) -> Texture {
+        procedural::petal(
+            TEXTURE_SIZE,
+            self.center.vec3(),
+            self.edge.vec3(),
+            self.streaks,
+            self.seed,
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SkinPattern {
+    pub base: Color,
+    pub speckle: Color,
+    pub speckles: f32,
+    pub seed: u32,
+}
+
+impl SkinPattern {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        let base = Color::rgb(rng.gen(), rng.gen(), rng.gen());
+
+        Self {
+            base,
+            speckle: Color::near(rng, base, 0.4),
+            speckles: rng.gen_range(0.0..1.0),
+            seed: rng.gen(),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.base.mutate(rng, variance);
+        self.speckle.mutate(rng, variance);
+        self.speckles += rng.gen_range(-0.25..0.25) * variance;
+        self.speckles = self.speckles.clamp(0.0, 1.0);
+    }
+
+    pub fn view(&self, callback: &Callback<SkinPattern>) -> Html {
+        html! {
+            <>
+                { color("Skin", &self.base, self, callback, |p, x| p.base = x) }
+                { color("Speckle", &self.speckle, self, callback, |p, x| p.speckle = x) }
+                { slider("Speckles", 0.0, 1.0, self.speckles, self, callback, |p, x| p.speckles = x) }
+            </>
+        }
+    }
+
+    pub fn texture(&self) -> Texture {
+        procedural::skin(
+            TEXTURE_SIZE,
+            self.base.vec3(),
+            self.speckle.vec3(),
+            self.speckles,
+            self.seed,
+        )
+    }
+}
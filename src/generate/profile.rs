@@ -0,0 +1,183 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use glam::Vec2;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::components::Slider;
+
+const MAX_RIBS: f32 = 32.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Profile {
+    Circle,
+    Ellipse { ratio: f32 },
+    Square,
+    Ribbed { ribs: f32, depth: f32 },
+    Flattened { ratio: f32 },
+}
+
+impl Profile {
+    pub const NAMES: [&'static str; 5] = ["Circle", "Ellipse", "Square", "Ribbed", "Flattened"];
+
+    pub fn new(rng: &mut impl Rng) -> Self {
+        if rng.gen_range(0.0..1.0) < 0.6 {
+            return Self::Circle;
+        }
+
+        let mut profile = Self::from_name(Self::NAMES[rng.gen_range(1..Self::NAMES.len())]);
+        profile.perturb(rng, 1.0);
+        profile
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "Circle" => Self::Circle,
+            "Ellipse" => Self::Ellipse { ratio: 0.6 },
+            "Square" => Self::Square,
+            "Ribbed" => Self::Ribbed {
+                ribs: 8.0,
+                depth: 0.2,
+            },
+            "Flattened" => Self::Flattened { ratio: 0.3 },
+            _ => panic!("invalid profile"),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Circle => "Circle",
+            Self::Ellipse { .. } => "Ellipse",
+            Self::Square => "Square",
+            Self::Ribbed { .. } => "Ribbed",
+            Self::Flattened { .. } => "Flattened",
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        if rng.gen_range(0.0..1.0) < 0.05 * variance {
+            *self = Self::new(rng);
+        } else {
+            self.perturb(rng, variance);
+        }
+    }
+
+    fn perturb(&mut self, rng: &mut impl Rng, variance: f32) {
+        match self {
+            Self::Ellipse { ratio } | Self::Flattened { ratio } => {
+                *ratio = (*ratio + rng.gen_range(-0.2..0.2) * variance).clamp(0.05, 1.0);
+            }
+            Self::Ribbed { ribs, depth } => {
+                *ribs = (*ribs + rng.gen_range(-2.0..2.0) * variance).clamp(3.0, MAX_RIBS);
+                *depth = (*depth + rng.gen_range(-0.1..0.1) * variance).clamp(0.0, 0.9);
+            }
+            _ => {}
+        }
+    }
+
+    fn ribs(&self) -> u32 {
+        match self {
+            Self::Ribbed { ribs, .. } => ribs.round().clamp(1.0, MAX_RIBS) as u32,
+            _ => 0,
+        }
+    }
+
+    pub fn radial(&self, radial: u32) -> u32 {
+        let step = match self {
+            Self::Circle | Self::Ellipse { .. } => 1,
+            Self::Square | Self::Flattened { .. } => 4,
+            Self::Ribbed { .. } => self.ribs() * 2,
+        };
+
+        let radial = match self {
+            Self::Ribbed { .. } => radial.max(step * 2),
+            _ => radial,
+        };
+
+        radial.div_ceil(step) * step
+    }
+
+    pub fn offset(&self, angle: f32) -> Vec2 {
+        let (sin, cos) = angle.sin_cos();
+
+        match self {
+            Self::Circle => Vec2::new(cos, sin),
+            Self::Ellipse { ratio } => Vec2::new(cos, sin * ratio),
+            Self::Square => {
+                let d = (cos.abs() + sin.abs()).max(f32::EPSILON);
+                Vec2::new(cos, sin) / d
+            }
+            Self::Ribbed { depth, .. } => {
+                let ribs = self.ribs() as f32;
+                let ridge = (angle * ribs / 2.0).cos().abs();
+                Vec2::new(cos, sin) * (1.0 - depth * (1.0 - ridge))
+            }
+            Self::Flattened { ratio } => {
+                let ratio = ratio.clamp(0.0, 1.0);
+                let quarter = (angle.rem_euclid(TAU) / FRAC_PI_2 + 1e-3) as u32 % 4;
+
+                let center = match quarter {
+                    1 | 2 => -(1.0 - ratio),
+                    _ => 1.0 - ratio,
+                };
+
+                Vec2::new(center + cos * ratio, sin * ratio)
+            }
+        }
+    }
+
+    #[allow(clippy::unnecessary_operation)]
+    pub fn view(&self, callback: &Callback<Profile>) -> Html {
+        let opts = Self::NAMES.into_iter().map(|name| {
+            let onclick = callback.reform(move |_| Self::from_name(name));
+
+            html! {
+                <option { onclick } selected={ name == self.name() }>{ name }</option>
+            }
+        });
+
+        let slider = |name: &str, min: f32, max: f32, value: f32, f: Box<dyn Fn(f32) -> Self>| {
+            html! {
+                <div class="property">
+                    { name.to_string() }
+                    <Slider { min } { max } { value } oninput={ callback.reform(f) } />
+                </div>
+            }
+        };
+
+        let properties = match *self {
+            Self::Ellipse { ratio } => slider(
+                "Ratio",
+                0.05,
+                1.0,
+                ratio,
+                Box::new(|ratio| Self::Ellipse { ratio }),
+            ),
+            Self::Ribbed { ribs, depth } => html! {
+                <>
+                    { slider("Ribs", 3.0, MAX_RIBS, ribs, Box::new(move |ribs| Self::Ribbed { ribs, depth })) }
+                    { slider("Rib Depth", 0.0, 0.9, depth, Box::new(move |depth| Self::Ribbed { ribs, depth })) }
+                </>
+            },
+            Self::Flattened { ratio } => slider(
+                "Thickness",
+                0.05,
+                1.0,
+                ratio,
+                Box::new(|ratio| Self::Flattened { ratio }),
+            ),
+            _ => html!(),
+        };
+
+        html! {
+            <>
+                <div class="property">
+                    { "Profile" }
+                    <select>{ for opts }</select>
+                </div>
+                { properties }
+            </>
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use glam::Vec3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+use crate::{components::Slider, mesh::Mesh};
+
+use super::{GenerateContext, PlantDna, PlantMessage, Scope};
+
+pub const MAX_RECURSION: u32 = 8;
+
+const MIN_SCALE: f32 = 0.01;
+
+const MIN_REF_SCALE: f32 = 0.1;
+
+const MAX_VERTICES: usize = 1 << 20;
+
+fn name_input<T: 'static>(name: &str, callback: &Callback<T>, f: fn(String) -> T) -> Html {
+    let oninput = callback.reform(move |event: InputEvent| {
+        f(event.target_unchecked_into::<HtmlInputElement>().value())
+    });
+
+    html! {
+        <div class="property">
+            { "Name" }
+            <input
+                class="text-input"
+                type="text"
+                value={ name.to_string() }
+                { oninput }
+            />
+        </div>
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Named {
+    pub name: String,
+    pub value: Box<PlantDna>,
+}
+
+impl Named {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            name: String::from("plant"),
+            value: Box::new(PlantDna::new(rng)),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.value.mutate(rng, variance);
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            name: if rng.gen() {
+                self.name.clone()
+            } else {
+                other.name.clone()
+            },
+            value: Box::new(self.value.crossover(&other.value, rng)),
+        }
+    }
+
+    pub fn view(&self, callback: &Callback<NamedMessage>) -> Html {
+        html! {
+            <>
+                { name_input(&self.name, callback, NamedMessage::SetName) }
+                { self.value.view(&callback.reform(|msg| NamedMessage::ChangeValue(Box::new(msg)))) }
+            </>
+        }
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let scope = Scope {
+            name: &self.name,
+            dna: &self.value,
+            parent: ctx.scope,
+        };
+
+        let ctx = GenerateContext {
+            scope: Some(&scope),
+            ..ctx
+        };
+
+        self.value.generate_mesh(mesh, start, direction, up, ctx);
+    }
+}
+
+pub enum NamedMessage {
+    SetName(String),
+    ChangeValue(Box<PlantMessage>),
+}
+
+impl NamedMessage {
+    pub fn handle(self, rng: &mut impl Rng, named: &mut Named) {
+        match self {
+            Self::SetName(name) => named.name = name,
+            Self::ChangeValue(msg) => msg.handle(rng, &mut named.value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ref {
+    pub name: String,
+    pub scale: f32,
+    pub max_depth: u32,
+}
+
+impl Ref {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            name: String::from("plant"),
+            scale: rng.gen_range(0.3..0.9),
+            max_depth: rng.gen_range(1..=4),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.scale += rng.gen_range(-0.25..0.25) * variance;
+        self.scale = self.scale.clamp(MIN_REF_SCALE, 1.0);
+
+        if rng.gen_range(0.0..1.0) < 0.5 * variance {
+            self.max_depth = (self.max_depth as i32 + rng.gen_range(-1..=1))
+                .clamp(1, MAX_RECURSION as i32) as u32;
+        }
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            name: if rng.gen() {
+                self.name.clone()
+            } else {
+                other.name.clone()
+            },
+            scale: if rng.gen() { self.scale } else { other.scale },
+            max_depth: if rng.gen() {
+                self.max_depth
+            } else {
+                other.max_depth
+            },
+        }
+    }
+
+    #[allow(clippy::unnecessary_operation)]
+    pub fn view(&self, callback: &Callback<RefMessage>) -> Html {
+        html! {
+            <>
+                { name_input(&self.name, callback, RefMessage::SetName) }
+                <div class="property">
+                    { "Scale" }
+                    <Slider
+                        min={ MIN_REF_SCALE }
+                        max=1.0
+                        value={ self.scale }
+                        oninput={ callback.reform(RefMessage::SetScale) }
+                    />
+                </div>
+                <div class="property">
+                    { "Depth" }
+                    <Slider
+                        min=1.0
+                        max={ MAX_RECURSION as f32 }
+                        steps={ MAX_RECURSION as i32 - 1 }
+                        value={ self.max_depth as f32 }
+                        oninput={ callback.reform(|x: f32| RefMessage::SetMaxDepth(x.round() as u32)) }
+                    />
+                </div>
+            </>
+        }
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        if ctx.recursion >= self.max_depth.min(MAX_RECURSION) {
+            return;
+        }
+
+        let scale = ctx.scale * self.scale;
+
+        if scale < MIN_SCALE || mesh.vertices.len() > MAX_VERTICES {
+            return;
+        }
+
+        if let Some(dna) = ctx.lookup(&self.name) {
+            let ctx = GenerateContext {
+                scale,
+                recursion: ctx.recursion + 1,
+                ..ctx
+            };
+
+            dna.generate_mesh(mesh, start, direction, up, ctx);
+        }
+    }
+}
+
+#[allow(clippy::enum_variant_names)]
+pub enum RefMessage {
+    SetName(String),
+    SetScale(f32),
+    SetMaxDepth(u32),
+}
+
+impl RefMessage {
+    pub fn handle(self, reference: &mut Ref) {
+        match self {
+            Self::SetName(name) => reference.name = name,
+            Self::SetScale(x) => reference.scale = x,
+            Self::SetMaxDepth(x) => reference.max_depth = x,
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use glam::{Quat, Vec3};
+use rand::{prelude::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::mesh::{Mesh, Organ};
+
+use super::{
+    curve::{Curve, CurvePoint},
+    frame, tube, BarkPattern, Color, Gene, GenerateContext, Gradient, Material, PlantDna,
+    PlantMessage,
+};
+
+const CONTROL_POINTS: usize = 8;
+
+const MAX_DEPTH: u32 = 3;
+
+const MAX_LATERALS: u32 = 8;
+
+const MAX_ROOTS: u32 = 16;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Roots {
+    pub amount: Gene,
+    pub length: Gene,
+    pub radius: Gene,
+    pub spread: Gene,
+    pub gravitropism: Gene,
+    pub branching: Gene,
+    pub branch_angle: Gene,
+    pub thinning: Gene,
+    pub depth: Gene,
+    pub wiggle: Gene,
+    pub seed: u64,
+    pub color: Gradient,
+    pub bark: BarkPattern,
+    pub shoot: Box<PlantDna>,
+}
+
+impl Roots {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            amount: rng.gen_range(1.0..6.0).into(),
+            length: rng.gen_range(0.2..1.0).into(),
+            radius: rng.gen_range(0.02..0.1).into(),
+            spread: rng.gen_range(0.0..1.2).into(),
+            gravitropism: rng.gen_range(0.0..1.0).into(),
+            branching: rng.gen_range(0.0..4.0).into(),
+            branch_angle: rng.gen_range(0.3..1.2).into(),
+            thinning: rng.gen_range(0.3..0.7).into(),
+            depth: rng.gen_range(0.0..2.0).into(),
+            wiggle: rng.gen_range(0.0..0.5).into(),
+            seed: rng.gen(),
+            color: Gradient::near(rng, Color::rgb(0.9, 0.85, 0.75), 0.2),
+            bark: BarkPattern::new(rng),
+            shoot: Box::new(PlantDna::new(rng)),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.amount.mutate(rng, 1.0, variance);
+        self.length.mutate(rng, 0.25, variance);
+        self.radius.mutate(rng, 0.02, variance);
+        self.spread.mutate(rng, 0.25, variance);
+        self.gravitropism.mutate(rng, 0.25, variance);
+        self.branching.mutate(rng, 1.0, variance);
+        self.branch_angle.mutate(rng, 0.25, variance);
+        self.thinning.mutate(rng, 0.1, variance);
+        self.depth.mutate(rng, 0.5, variance);
+        self.wiggle.mutate(rng, 0.1, variance);
+        self.color.mutate(rng, variance);
+        self.bark.mutate(rng, variance);
+        self.shoot.mutate(rng, variance);
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            amount: self.amount.crossover(&other.amount, rng),
+            length: self.length.crossover(&other.length, rng),
+            radius: self.radius.crossover(&other.radius, rng),
+            spread: self.spread.crossover(&other.spread, rng),
+            gravitropism: self.gravitropism.crossover(&other.gravitropism, rng),
+            branching: self.branching.crossover(&other.branching, rng),
+            branch_angle: self.branch_angle.crossover(&other.branch_angle, rng),
+            thinning: self.thinning.crossover(&other.thinning, rng),
+            depth: self.depth.crossover(&other.depth, rng),
+            wiggle: self.wiggle.crossover(&other.wiggle, rng),
+            seed: if rng.gen() { self.seed } else { other.seed },
+            color: self.color.crossover(&other.color, rng),
+            bark: if rng.gen() {
+                self.bark.clone()
+            } else {
+                other.bark.clone()
+            },
+            shoot: Box::new(self.shoot.crossover(&other.shoot, rng)),
+        }
+    }
+
+    pub fn view(&self, callback: &Callback<RootsMessage>) -> Html {
+        html! {
+            <>
+                { self.amount.view("Amount", 1.0, MAX_ROOTS as f32, callback.reform(RootsMessage::SetAmount)) }
+                { self.length.view("Length", 0.1, 2.0, callback.reform(RootsMessage::SetLength)) }
+                { self.radius.view("Radius", 0.01, 0.3, callback.reform(RootsMessage::SetRadius)) }
+                { self.spread.view("Spread", 0.0, FRAC_PI_2, callback.reform(RootsMessage::SetSpread)) }
+                { self.gravitropism.view("Gravitropism", -1.0, 2.0, callback.reform(RootsMessage::SetGravitropism)) }
+                { self.branching.view("Branching", 0.0, MAX_LATERALS as f32, callback.reform(RootsMessage::SetBranching)) }
+                { self.branch_angle.view("Branch Angle", 0.0, FRAC_PI_2, callback.reform(RootsMessage::SetBranchAngle)) }
+                { self.thinning.view("Thinning", 0.1, 1.0, callback.reform(RootsMessage::SetThinning)) }
+                { self.depth.view("Depth", 0.0, MAX_DEPTH as f32, callback.reform(RootsMessage::SetDepth)) }
+                { self.wiggle.view("Wiggle", 0.0, 1.0, callback.reform(RootsMessage::SetWiggle)) }
+                { self.color.view(&callback.reform(RootsMessage::SetColor)) }
+                { self.bark.view(&callback.reform(RootsMessage::SetBark)) }
+                <div class="container">
+                    { self.shoot.view(&callback.reform(|msg| RootsMessage::ChangeShoot(Box::new(msg)))) }
+                </div>
+            </>
+        }
+    }
+
+    fn root(
+        &self,
+        mesh: &mut Mesh,
+        base: &CurvePoint,
+        level: u32,
+        rng: &mut StdRng,
+        ctx: &GenerateContext,
+    ) {
+        let (start, direction, up) = (base.position, base.direction, base.up);
+
+        let length = self.length.eval(ctx) * ctx.scale;
+        let radius = self.radius.eval(ctx) * ctx.scale;
+        let gravitropism = self.gravitropism.eval(ctx);
+        let wiggle = self.wiggle.eval(ctx);
+        let thinning = self.thinning.eval(ctx).clamp(0.0, 1.0);
+
+        if length <= 0.0 || radius <= 0.0 {
+            return;
+        }
+
+        let dt = 1.0 / CONTROL_POINTS as f32;
+
+        let mut position = start;
+        let mut heading = direction;
+        let mut control = vec![position];
+
+        for _ in 0..CONTROL_POINTS {
+            let noise = Vec3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+
+            let next = heading + (-Vec3::Y * gravitropism + noise * wiggle) * dt;
+            heading = next.try_normalize().unwrap_or(heading);
+
+            position += heading * length * dt;
+            control.push(position);
+        }
+
+        let curve = Curve::new(control, direction, up, 0.0);
+        let tessellation = ctx.tessellation;
+        let points = curve.adaptive(tessellation.ring_angle(radius), tessellation.curve_samples);
+
+        let end_radius = radius * thinning;
+        let radial = tessellation.radial(radius);
+        let first = mesh.vertices.len();
+
+        tube::tube(mesh, &points, radial, |point| {
+            radius * (1.0 - point.t) + end_radius * point.t
+        });
+
+        let rings = mesh.vertices[first..].chunks_mut(radial as usize + 1);
+
+        for (ring, point) in rings.zip(points.iter()) {
+            let color = self.color.at(point.t).to_array();
+
+            for vertex in ring {
+                vertex.color = color;
+            }
+        }
+
+        let vertices = first..mesh.vertices.len();
+        mesh.tag(vertices.clone(), Organ::Root);
+        ctx.apply_material(mesh, vertices, Material::Bark(self.bark.clone()));
+
+        let depth = self.depth.eval(ctx).round().clamp(0.0, MAX_DEPTH as f32) as u32;
+
+        if level >= depth || points.len() < 2 {
+            return;
+        }
+
+        let laterals = self
+            .branching
+            .eval(ctx)
+            .round()
+            .clamp(0.0, MAX_LATERALS as f32) as u32;
+        let branch_angle = self.branch_angle.eval(ctx);
+
+        for i in 0..laterals {
+            let t = 0.2 + 0.7 * (i as f32 + 0.5) / laterals as f32;
+            let point = &points[(t * (points.len() - 1) as f32).round() as usize];
+
+            let around = Quat::from_axis_angle(point.direction, rng.gen_range(0.0..TAU));
+            let out = around * point.up;
+
+            let (sin, cos) = branch_angle.sin_cos();
+            let lateral = (point.direction * cos + out * sin).normalize();
+            let lateral_up = frame::transport(point.up, point.direction, lateral);
+
+            let base = CurvePoint {
+                direction: lateral,
+                up: lateral_up,
+                ..*point
+            };
+
+            let ctx = GenerateContext {
+                scale: ctx.scale * thinning,
+                ..ctx.child()
+            };
+
+            self.root(mesh, &base, level + 1, rng, &ctx);
+        }
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let up = frame::orthogonalize(direction, up);
+
+        if ctx.roots {
+            let amount = (self.amount.eval(&ctx).round().max(0.0) as u32).min(MAX_ROOTS);
+            let spread = self.spread.eval(&ctx);
+
+            let down = -direction;
+            let mut rng = StdRng::seed_from_u64(self.seed);
+
+            for i in 0..amount {
+                let angle = i as f32 / amount as f32 * TAU + rng.gen_range(-0.5..0.5);
+                let out = Quat::from_axis_angle(down, angle) * up;
+
+                let (sin, cos) = spread.sin_cos();
+                let root = (down * cos + out * sin).normalize();
+
+                let base = CurvePoint {
+                    position: start,
+                    direction: root,
+                    up: frame::transport(up, direction, root),
+                    distance: 0.0,
+                    t: 0.0,
+                };
+
+                let ctx = GenerateContext {
+                    index: i,
+                    t: i as f32 / (amount.max(2) - 1) as f32,
+                    ..ctx.child()
+                };
+
+                self.root(mesh, &base, 0, &mut rng, &ctx);
+            }
+        }
+
+        self.shoot
+            .generate_mesh(mesh, start, direction, up, ctx.child());
+    }
+}
+
+pub enum RootsMessage {
+    SetAmount(Gene),
+    SetLength(Gene),
+    SetRadius(Gene),
+    SetSpread(Gene),
+    SetGravitropism(Gene),
+    SetBranching(Gene),
+    SetBranchAngle(Gene),
+    SetThinning(Gene),
+    SetDepth(Gene),
+    SetWiggle(Gene),
+    SetColor(Gradient),
+    SetBark(BarkPattern),
+    ChangeShoot(Box<PlantMessage>),
+}
+
+impl RootsMessage {
+    pub fn handle(self, rng: &mut impl Rng, roots: &mut Roots) {
+        match self {
+            Self::SetAmount(x) => roots.amount = x,
+            Self::SetLength(x) => roots.length = x,
+            Self::SetRadius(x) => roots.radius = x,
+            Self::SetSpread(x) => roots.spread = x,
+            Self::SetGravitropism(x) => roots.gravitropism = x,
+            Self::SetBranching(x) => roots.branching = x,
+            Self::SetBranchAngle(x) => roots.branch_angle = x,
+            Self::SetThinning(x) => roots.thinning = x,
+            Self::SetDepth(x) => roots.depth = x,
+            Self::SetWiggle(x) => roots.wiggle = x,
+            Self::SetColor(x) => roots.color = x,
+            Self::SetBark(x) => roots.bark = x,
+            Self::ChangeShoot(msg) => msg.handle(rng, &mut roots.shoot),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::f32::consts::PI;
+
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::components::Slider;
+
+const REFERENCE_RADIUS: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TessellationSettings {
+    pub radial: u32,
+    pub ring_angle: f32,
+    pub curve_samples: usize,
+    pub leaf_steps: u32,
+}
+
+impl TessellationSettings {
+    pub fn radial(&self, radius: f32) -> u32 {
+        let radial = self.radial as f32 * (radius / REFERENCE_RADIUS).sqrt();
+        (radial.round() as u32).clamp(3, self.radial.max(3))
+    }
+
+    pub fn ring_angle(&self, radius: f32) -> f32 {
+        let angle = self.ring_angle * (REFERENCE_RADIUS / radius.max(0.001)).sqrt();
+        angle.min(PI / 4.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Tessellation {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Custom(TessellationSettings),
+}
+
+impl Tessellation {
+    pub fn settings(&self) -> TessellationSettings {
+        match self {
+            Self::Low => TessellationSettings {
+                radial: 5,
+                ring_angle: PI / 8.0,
+                curve_samples: 16,
+                leaf_steps: 5,
+            },
+            Self::Medium => TessellationSettings {
+                radial: 8,
+                ring_angle: PI / 18.0,
+                curve_samples: 32,
+                leaf_steps: 8,
+            },
+            Self::High => TessellationSettings {
+                radial: 16,
+                ring_angle: PI / 36.0,
+                curve_samples: 64,
+                leaf_steps: 16,
+            },
+            Self::Custom(settings) => *settings,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Low => "Low",
+            Self::Medium => "Medium",
+            Self::High => "High",
+            Self::Custom(_) => "Custom",
+        }
+    }
+
+    #[allow(clippy::unnecessary_operation)]
+    pub fn view(&self, callback: &Callback<Tessellation>) -> Html {
+        let settings = self.settings();
+
+        let opts = [Self::Low, Self::Medium, Self::High, Self::Custom(settings)];
+
+        let opts = opts.into_iter().map(|opt| {
+            let onclick = callback.reform(move |_| opt);
+
+            html! {
+                <option { onclick } selected={ opt.name() == self.name() }>{ opt.name() }</option>
+            }
+        });
+
+        let custom = match self {
+            Self::Custom(settings) => {
+                let settings = *settings;
+
+                html! {
+                    <>
+                        <div class="property">
+                            { "Radial" }
+                            <Slider
+                                min=3.0
+                                max=32.0
+                                steps=29
+                                value={ settings.radial as f32 }
+                                oninput={ callback.reform(move |x: f32| Self::Custom(TessellationSettings {
+                                    radial: x.round() as u32,
+                                    ..settings
+                                })) }
+                            />
+                        </div>
+                        <div class="property">
+                            { "Ring Angle" }
+                            <Slider
+                                min=0.01
+                                max={ PI / 4.0 }
+                                value={ settings.ring_angle }
+                                oninput={ callback.reform(move |x| Self::Custom(TessellationSettings {
+                                    ring_angle: x,
+                                    ..settings
+                                })) }
+                            />
+                        </div>
+                        <div class="property">
+                            { "Curve Samples" }
+                            <Slider
+                                min=4.0
+                                max=128.0
+                                steps=124
+                                value={ settings.curve_samples as f32 }
+                                oninput={ callback.reform(move |x: f32| Self::Custom(TessellationSettings {
+                                    curve_samples: x.round() as usize,
+                                    ..settings
+                                })) }
+                            />
+                        </div>
+                        <div class="property">
+                            { "Leaf Steps" }
+                            <Slider
+                                min=2.0
+                                max=32.0
+                                steps=30
+                                value={ settings.leaf_steps as f32 }
+                                oninput={ callback.reform(move |x: f32| Self::Custom(TessellationSettings {
+                                    leaf_steps: x.round() as u32,
+                                    ..settings
+                                })) }
+                            />
+                        </div>
+                    </>
+                }
+            }
+            _ => html!(),
+        };
+
+        html! {
+            <>
+                <div class="property">
+                    { "Quality" }
+                    <select>{ for opts }</select>
+                </div>
+                { custom }
+            </>
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    cell::Cell,
+    f32::consts::{FRAC_PI_2, TAU},
+};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::mesh::{Mesh, Vertex};
+
+use super::{curve::CurvePoint, profile::Profile};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ring {
+    pub first: u32,
+    pub radial: u32,
+}
+
+#[derive(Debug)]
+pub struct Joint {
+    pub ring: Ring,
+    pub welded: Cell<bool>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cap {
+    None,
+    Flat,
+    Hemisphere,
+}
+
+impl Cap {
+    pub const ALL: [Self; 3] = [Self::None, Self::Flat, Self::Hemisphere];
+
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self::ALL[rng.gen_range(0..Self::ALL.len())]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Flat => "Flat",
+            Self::Hemisphere => "Hemisphere",
+        }
+    }
+
+    pub fn close(
+        &self,
+        mesh: &mut Mesh,
+        ring: Ring,
+        point: &CurvePoint,
+        profile: &Profile,
+        radius: f32,
+        tip: bool,
+    ) {
+        let outward = if tip {
+            point.direction
+        } else {
+            -point.direction
+        };
+
+        let edge = match self {
+            Self::None => return,
+            Self::Flat => {
+                let first = mesh.vertices.len() as u32;
+
+                for j in 0..=ring.radial {
+                    let vertex = mesh.vertices[(ring.first + j) as usize];
+                    mesh.vertices.push(vertex);
+                }
+
+                Ring { first, ..ring }
+            }
+            Self::Hemisphere => {
+                let steps = (ring.radial / 4).max(2);
+                let right = point.right();
+
+                let mut previous = ring;
+
+                for k in 1..steps {
+                    let angle = k as f32 / steps as f32 * FRAC_PI_2;
+                    let (sin, cos) = angle.sin_cos();
+
+                    let center = point.position + outward * radius * sin;
+                    let first = mesh.vertices.len() as u32;
+
+                    for j in 0..=ring.radial {
+                        let u = j as f32 / ring.radial as f32;
+                        let offset = profile.offset(u * TAU) * radius * cos;
+
+                        let mut vertex = mesh.vertices[(ring.first + j) as usize];
+                        vertex.position = (center + right * offset.x + point.up * offset.y).into();
+                        mesh.vertices.push(vertex);
+                    }
+
+                    let current = Ring { first, ..ring };
+                    mesh.seams.push([first, first + ring.radial]);
+
+                    if tip {
+                        stitch(mesh, previous, current);
+                    } else {
+                        stitch(mesh, current, previous);
+                    }
+
+                    previous = current;
+                }
+
+                previous
+            }
+        };
+
+        let mut center = mesh.vertices[edge.first as usize];
+        center.position = (point.position + outward * radius * self.depth()).into();
+        center.uv[0] = 0.5;
+
+        let apex = mesh.vertices.len() as u32;
+        mesh.vertices.push(center);
+
+        for j in 0..edge.radial {
+            let (a, b) = (edge.first + j, edge.first + j + 1);
+
+            mesh.indices.push(apex);
+
+            if tip {
+                mesh.indices.push(a);
+                mesh.indices.push(b);
+            } else {
+                mesh.indices.push(b);
+                mesh.indices.push(a);
+            }
+        }
+    }
+
+    fn depth(&self) -> f32 {
+        match self {
+            Self::None | Self::Flat => 0.0,
+            Self::Hemisphere => 1.0,
+        }
+    }
+}
+
+pub fn tube(
+    mesh: &mut Mesh,
+    points: &[CurvePoint],
+    radial: u32,
+    radius: impl Fn(&CurvePoint) -> f32,
+) {
+    profiled(mesh, points, radial, &Profile::Circle, radius, None);
+}
+
+pub fn profiled(
+    mesh: &mut Mesh,
+    points: &[CurvePoint],
+    radial: u32,
+    profile: &Profile,
+    radius: impl Fn(&CurvePoint) -> f32,
+    start: Option<Ring>,
+) -> Ring {
+    let circumference = points
+        .first()
+        .map(|point| radius(point) * TAU)
+        .unwrap_or(1.0)
+        .max(f32::EPSILON);
+
+    let mut previous = start;
+
+    for (i, point) in points.iter().enumerate() {
+        if i == 0 && start.is_some() {
+            continue;
+        }
+
+        let right = point.right();
+        let radius = radius(point);
+
+        let ring = Ring {
+            first: mesh.vertices.len() as u32,
+            radial,
+        };
+
+        for j in 0..=radial {
+            let u = j as f32 / radial as f32;
+            let offset = profile.offset(u * TAU) * radius;
+
+            let p = point.position + right * offset.x + point.up * offset.y;
+
+            mesh.vertices.push(Vertex {
+                position: p.into(),
+                normal: [0.0; 3],
+                uv: [u, point.distance / circumference],
+                ..Default::default()
+            });
+        }
+
+        mesh.seams.push([ring.first, ring.first + radial]);
+
+        if let Some(previous) = previous {
+            stitch(mesh, previous, ring);
+        }
+
+        previous = Some(ring);
+    }
+
+    previous.unwrap_or(Ring {
+        first: mesh.vertices.len() as u32,
+        radial,
+    })
+}
+
+fn stitch(mesh: &mut Mesh, previous: Ring, next: Ring) {
+    for j in 0..next.radial {
+        let i0 = next.first + j;
+        let i1 = next.first + j + 1;
+        let i2 = previous.first + j;
+        let i3 = previous.first + j + 1;
+
+        mesh.indices.push(i0);
+        mesh.indices.push(i2);
+        mesh.indices.push(i1);
+
+        mesh.indices.push(i1);
+        mesh.indices.push(i2);
+        mesh.indices.push(i3);
+    }
+}
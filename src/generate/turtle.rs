@@ -0,0 +1,186 @@
+use std::f32::consts::PI;
+
+use glam::{Quat, Vec3};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::mesh::Mesh;
+
+use super::{frame, Gene, GenerateContext, PlantDna, PlantMessage};
+
+pub fn turn(direction: Vec3, up: Vec3, yaw: f32, pitch: f32, roll: f32) -> (Vec3, Vec3) {
+    let up = frame::orthogonalize(direction, up);
+
+    let direction = Quat::from_axis_angle(up, yaw) * direction;
+    let right = direction.cross(up);
+
+    let pitch = Quat::from_axis_angle(right, pitch);
+    let (direction, up) = (pitch * direction, pitch * up);
+
+    let up = Quat::from_axis_angle(direction, roll) * up;
+
+    (direction.normalize(), up.normalize())
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Turn {
+    pub yaw: Gene,
+    pub pitch: Gene,
+    pub roll: Gene,
+    pub value: Box<PlantDna>,
+}
+
+impl Turn {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            yaw: rng.gen_range(-0.5..0.5).into(),
+            pitch: rng.gen_range(-0.5..0.5).into(),
+            roll: 0.0.into(),
+            value: Box::new(PlantDna::new(rng)),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.yaw.mutate(rng, 0.25, variance);
+        self.pitch.mutate(rng, 0.25, variance);
+        self.roll.mutate(rng, 0.25, variance);
+        self.value.mutate(rng, variance);
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            yaw: self.yaw.crossover(&other.yaw, rng),
+            pitch: self.pitch.crossover(&other.pitch, rng),
+            roll: self.roll.crossover(&other.roll, rng),
+            value: Box::new(self.value.crossover(&other.value, rng)),
+        }
+    }
+
+    pub fn view(&self, callback: &Callback<TurnMessage>) -> Html {
+        html! {
+            <>
+                { self.yaw.view("Yaw", -PI, PI, callback.reform(TurnMessage::SetYaw)) }
+                { self.pitch.view("Pitch", -PI, PI, callback.reform(TurnMessage::SetPitch)) }
+                { self.roll.view("Roll", -PI, PI, callback.reform(TurnMessage::SetRoll)) }
+                { self.value.view(&callback.reform(|msg| TurnMessage::ChangeValue(Box::new(msg)))) }
+            </>
+        }
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let yaw = self.yaw.eval(&ctx);
+        let pitch = self.pitch.eval(&ctx);
+        let roll = self.roll.eval(&ctx);
+
+        let (direction, up) = turn(direction, up, yaw, pitch, roll);
+
+        self.value
+            .generate_mesh(mesh, start, direction, up, ctx.child());
+    }
+}
+
+pub enum TurnMessage {
+    SetYaw(Gene),
+    SetPitch(Gene),
+    SetRoll(Gene),
+    ChangeValue(Box<PlantMessage>),
+}
+
+impl TurnMessage {
+    pub fn handle(self, rng: &mut impl Rng, turn: &mut Turn) {
+        match self {
+            Self::SetYaw(x) => turn.yaw = x,
+            Self::SetPitch(x) => turn.pitch = x,
+            Self::SetRoll(x) => turn.roll = x,
+            Self::ChangeValue(msg) => msg.handle(rng, &mut turn.value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fork {
+    pub first: Box<PlantDna>,
+    pub second: Box<PlantDna>,
+}
+
+impl Fork {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            first: Box::new(PlantDna::new(rng)),
+            second: Box::new(PlantDna::new(rng)),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.first.mutate(rng, variance);
+        self.second.mutate(rng, variance);
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            first: Box::new(self.first.crossover(&other.first, rng)),
+            second: Box::new(self.second.crossover(&other.second, rng)),
+        }
+    }
+
+    pub fn view(&self, callback: &Callback<ForkMessage>) -> Html {
+        html! {
+            <>
+                <div class="container">
+                    { self.first.view(&callback.reform(|msg| ForkMessage::ChangeFirst(Box::new(msg)))) }
+                </div>
+                <div class="container">
+                    { self.second.view(&callback.reform(|msg| ForkMessage::ChangeSecond(Box::new(msg)))) }
+                </div>
+            </>
+        }
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let first = GenerateContext {
+            index: 0,
+            t: 0.0,
+            ..ctx.child()
+        };
+
+        self.first.generate_mesh(mesh, start, direction, up, first);
+
+        let ctx = GenerateContext {
+            joint: ctx.joint,
+            index: 1,
+            t: 1.0,
+            ..ctx.child()
+        };
+
+        self.second.generate_mesh(mesh, start, direction, up, ctx);
+    }
+}
+
+pub enum ForkMessage {
+    ChangeFirst(Box<PlantMessage>),
+    ChangeSecond(Box<PlantMessage>),
+}
+
+impl ForkMessage {
+    pub fn handle(self, rng: &mut impl Rng, fork: &mut Fork) {
+        match self {
+            Self::ChangeFirst(msg) => msg.handle(rng, &mut fork.first),
+            Self::ChangeSecond(msg) => msg.handle(rng, &mut fork.second),
+        }
+    }
+}
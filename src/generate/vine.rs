@@ -0,0 +1,647 @@
+use std::f32::consts::TAU;
+
+use glam::Vec3;
+use rand::{prelude::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use yew::prelude::*;
+
+use crate::mesh::{Mesh, Organ, TriangleGrid, Vertex};
+
+use super::{
+    curve::CurvePoint,
+    frame,
+    profile::Profile,
+    tube::{self, Cap, Ring},
+    BarkPattern, Color, Gene, GenerateContext, Gradient, Material, PlantDna, PlantMessage,
+};
+
+const MAX_STEPS: usize = 1000;
+
+const REACH: f32 = 3.0;
+
+const TIP_RADIUS: f32 = 0.3;
+
+const TENDRIL_RADIUS: f32 = 0.2;
+
+const TENDRIL_SAMPLES: usize = 24;
+
+const TRELLIS_BARS: usize = 5;
+
+const MAX_TRELLIS_ROWS: usize = 64;
+
+const MIN_SUPPORT_WIDTH: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Support {
+    None,
+    Pole,
+    Wall,
+    Trellis,
+}
+
+impl Support {
+    pub const ALL: [Self; 4] = [Self::None, Self::Pole, Self::Wall, Self::Trellis];
+
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self::ALL[rng.gen_range(1..Self::ALL.len())]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Pole => "Pole",
+            Self::Wall => "Wall",
+            Self::Trellis => "Trellis",
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn mesh(
+        &self,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        width: f32,
+        height: f32,
+        radius: f32,
+        gap: f32,
+        ctx: &GenerateContext,
+    ) -> Option<Mesh> {
+        let right = direction.cross(up);
+        let mut mesh = Mesh::default();
+
+        match self {
+            Self::None => return None,
+            Self::Pole => {
+                let base = start - up * (radius + gap);
+                bar(&mut mesh, base, direction, up, height, radius, ctx);
+            }
+            Self::Wall => {
+                let base = start - up * gap;
+
+                for (u, v) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+                    let p = base + right * (u - 0.5) * width + direction * v * height;
+
+                    mesh.vertices.push(Vertex {
+                        position: p.into(),
+                        uv: [u * width, v * height],
+                        ..Default::default()
+                    });
+                }
+
+                mesh.indices.extend_from_slice(&[0, 1, 2, 0, 2, 3]);
+            }
+            Self::Trellis => {
+                let base = start - up * (radius + gap);
+                let spacing = width / (TRELLIS_BARS - 1) as f32;
+
+                for i in 0..TRELLIS_BARS {
+                    let offset = right * (i as f32 * spacing - width / 2.0);
+                    bar(&mut mesh, base + offset, direction, up, height, radius, ctx);
+                }
+
+                let rows = ((height / spacing).floor() as usize).min(MAX_TRELLIS_ROWS);
+
+                for i in 0..=rows {
+                    let p = base + direction * (i as f32 * spacing) - right * (width / 2.0);
+                    bar(&mut mesh, p, right, up, width, radius, ctx);
+                }
+            }
+        }
+
+        Some(mesh)
+    }
+}
+
+fn bar(
+    mesh: &mut Mesh,
+    start: Vec3,
+    direction: Vec3,
+    up: Vec3,
+    length: f32,
+    radius: f32,
+    ctx: &GenerateContext,
+) {
+    let points = [0.0, 1.0].map(|t| CurvePoint {
+        position: start + direction * length * t,
+        direction,
+        up,
+        distance: length * t,
+        t,
+    });
+
+    let radial = ctx.tessellation.radial(radius);
+    let first = Ring {
+        first: mesh.vertices.len() as u32,
+        radial,
+    };
+    let last = tube::profiled(mesh, &points, radial, &Profile::Circle, |_| radius, None);
+
+    Cap::Flat.close(mesh, first, &points[0], &Profile::Circle, radius, false);
+    Cap::Flat.close(mesh, last, &points[1], &Profile::Circle, radius, true);
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Vine {
+    pub support: Support,
+    pub support_width: Gene,
+    pub support_height: Gene,
+    pub support_radius: Gene,
+    pub show_support: bool,
+    pub length: Gene,
+    pub radius: Gene,
+    pub step: Gene,
+    pub climb: Gene,
+    pub wrap: Gene,
+    pub wander: Gene,
+    pub leaf_spacing: Gene,
+    pub tendril_length: Gene,
+    pub tendril_coils: Gene,
+    pub seed: u64,
+    pub color: Gradient,
+    pub support_color: Color,
+    pub bark: BarkPattern,
+    pub leaf: Box<PlantDna>,
+}
+
+impl Vine {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            support: Support::new(rng),
+            support_width: rng.gen_range(0.5..2.0).into(),
+            support_height: rng.gen_range(1.0..3.0).into(),
+            support_radius: rng.gen_range(0.02..0.1).into(),
+            show_support: true,
+            length: rng.gen_range(1.0..4.0).into(),
+            radius: rng.gen_range(0.003..0.015).into(),
+            step: rng.gen_range(0.02..0.06).into(),
+            climb: rng.gen_range(0.5..3.0).into(),
+            wrap: rng.gen_range(-2.0..2.0).into(),
+            wander: rng.gen_range(0.0..3.0).into(),
+            leaf_spacing: rng.gen_range(0.05..0.3).into(),
+            tendril_length: rng.gen_range(0.0..0.15).into(),
+            tendril_coils: rng.gen_range(1.0..5.0).into(),
+            seed: rng.gen(),
+            color: Gradient::near(rng, Color::rgb(0.8, 0.9, 0.7), 0.2),
+            support_color: Color::near(rng, Color::rgb(0.6, 0.5, 0.4), 0.2),
+            bark: BarkPattern::new(rng),
+            leaf: Box::new(PlantDna::new(rng)),
+        }
+    }
+
+    pub fn mutate(&mut self, rng: &mut impl Rng, variance: f32) {
+        self.support_width.mutate(rng, 0.25, variance);
+        self.support_height.mutate(rng, 0.5, variance);
+        self.support_radius.mutate(rng, 0.02, variance);
+        self.length.mutate(rng, 0.5, variance);
+        self.radius.mutate(rng, 0.002, variance);
+        self.step.mutate(rng, 0.01, variance);
+        self.climb.mutate(rng, 0.5, variance);
+        self.wrap.mutate(rng, 0.5, variance);
+        self.wander.mutate(rng, 0.5, variance);
+        self.leaf_spacing.mutate(rng, 0.05, variance);
+        self.tendril_length.mutate(rng, 0.03, variance);
+        self.tendril_coils.mutate(rng, 0.5, variance);
+        self.color.mutate(rng, variance);
+        self.support_color.mutate(rng, variance);
+        self.bark.mutate(rng, variance);
+        self.leaf.mutate(rng, variance);
+
+        if rng.gen_range(0.0..1.0) < 0.05 * variance {
+            self.support = Support::new(rng);
+        }
+    }
+
+    pub fn crossover(&self, other: &Self, rng: &mut impl Rng) -> Self {
+        Self {
+            support: if rng.gen() {
+                self.support
+            } else {
+                other.support
+            },
+            support_width: self.support_width.crossover(&other.support_width, rng),
+            support_height: self.support_height.crossover(&other.support_height, rng),
+            support_radius: self.support_radius.crossover(&other.support_radius, rng),
+            show_support: if rng.gen() {
+                self.show_support
+            } else {
+                other.show_support
+            },
+            length: self.length.crossover(&other.length, rng),
+            radius: self.radius.crossover(&other.radius, rng),
+            step: self.step.crossover(&other.step, rng),
+            climb: self.climb.crossover(&other.climb, rng),
+            wrap: self.wrap.crossover(&other.wrap, rng),
+            wander: self.wander.crossover(&other.wander, rng),
+            leaf_spacing: self.leaf_spacing.crossover(&other.leaf_spacing, rng),
+            tendril_length: self.tendril_length.crossover(&other.tendril_length, rng),
+            tendril_coils: self.tendril_coils.crossover(&other.tendril_coils, rng),
+            seed: if rng.gen() { self.seed } else { other.seed },
+            color: self.color.crossover(&other.color, rng),
+            support_color: self.support_color.crossover(&other.support_color, rng),
+            bark: if rng.gen() {
+                self.bark.clone()
+            } else {
+                other.bark.clone()
+            },
+            leaf: Box::new(self.leaf.crossover(&other.leaf, rng)),
+        }
+    }
+
+    pub fn view(&self, callback: &Callback<VineMessage>) -> Html {
+        let supports = Support::ALL.into_iter().map(|support| {
+            let onclick = callback.reform(move |_| VineMessage::SetSupport(support));
+
+            html! {
+                <option { onclick } selected={ support == self.support }>{ support.name() }</option>
+            }
+        });
+
+        let show_support = !self.show_support;
+        let onclick = callback.reform(move |_| VineMessage::SetShowSupport(show_support));
+
+        let width = self.support_width.view(
+            "Support Width",
+            MIN_SUPPORT_WIDTH,
+            5.0,
+            callback.reform(VineMessage::SetSupportWidth),
+        );
+        let radius = self.support_radius.view(
+            "Support Radius",
+            0.005,
+            0.5,
+            callback.reform(VineMessage::SetSupportRadius),
+        );
+
+        let dimensions = match self.support {
+            Support::None => html!(),
+            Support::Pole => radius,
+            Support::Wall => width,
+            Support::Trellis => html! { <>{ width }{ radius }</> },
+        };
+
+        html! {
+            <>
+                <div class="property">
+                    { "Support" }
+                    <select>{ for supports }</select>
+                </div>
+                { dimensions }
+                { self.support_height.view("Support Height", 0.1, 5.0, callback.reform(VineMessage::SetSupportHeight)) }
+                <div class="property">
+                    { "Show Support" }
+                    <input type="checkbox" checked={ self.show_support } { onclick } />
+                </div>
+                { self.support_color.view("Support Color", callback.reform(VineMessage::SetSupportColor)) }
+                { self.length.view("Length", 0.1, 10.0, callback.reform(VineMessage::SetLength)) }
+                { self.radius.view("Radius", 0.001, 0.05, callback.reform(VineMessage::SetRadius)) }
+                { self.step.view("Step", 0.005, 0.2, callback.reform(VineMessage::SetStep)) }
+                { self.climb.view("Climb", 0.0, 10.0, callback.reform(VineMessage::SetClimb)) }
+                { self.wrap.view("Wrap", -10.0, 10.0, callback.reform(VineMessage::SetWrap)) }
+                { self.wander.view("Wander", 0.0, 10.0, callback.reform(VineMessage::SetWander)) }
+                { self.leaf_spacing.view("Leaf Spacing", 0.01, 1.0, callback.reform(VineMessage::SetLeafSpacing)) }
+                { self.tendril_length.view("Tendril Length", 0.0, 0.5, callback.reform(VineMessage::SetTendrilLength)) }
+                { self.tendril_coils.view("Tendril Coils", 0.0, 10.0, callback.reform(VineMessage::SetTendrilCoils)) }
+                { self.color.view(&callback.reform(VineMessage::SetColor)) }
+                { self.bark.view(&callback.reform(VineMessage::SetBark)) }
+                <div class="container">
+                    { self.leaf.view(&callback.reform(|msg| VineMessage::ChangeLeaf(Box::new(msg)))) }
+                </div>
+            </>
+        }
+    }
+
+    fn grow(
+        &self,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        obstacle: Option<&TriangleGrid>,
+        ctx: &GenerateContext,
+    ) -> Vec<CurvePoint> {
+        let length = self.length.eval(ctx).max(0.0) * ctx.scale;
+        let step = self.step.eval(ctx).max(0.001) * ctx.scale;
+        let radius = self.radius.eval(ctx).max(0.0) * ctx.scale;
+        let climb = self.climb.eval(ctx);
+        let wrap = self.wrap.eval(ctx);
+        let wander = self.wander.eval(ctx).max(0.0);
+
+        let steps = ((length / step).ceil() as usize).min(MAX_STEPS);
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let contact = |p: Vec3| {
+            let (closest, normal) = obstacle?.closest_point(p, step * REACH + radius)?;
+            let offset = p - closest;
+
+            let away = offset.normalize_or_zero();
+            let away = if away == Vec3::ZERO { normal } else { away };
+
+            Some((closest + away * radius, away))
+        };
+
+        let mut position = start;
+        let mut heading = direction;
+        let (mut normal, mut attached) = match contact(start) {
+            Some((_, away)) => (away, true),
+            None => (up, false),
+        };
+
+        let mut points = vec![CurvePoint {
+            position,
+            direction: heading,
+            up: frame::orthogonalize(heading, normal),
+            distance: 0.0,
+            t: 0.0,
+        }];
+
+        for i in 1..=steps {
+            let side = direction.cross(normal).normalize_or_zero();
+            let noise = Vec3::new(
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+                rng.gen_range(-1.0..1.0),
+            );
+
+            let mut turned = heading + (direction * climb + side * wrap + noise * wander) * step;
+
+            if attached {
+                turned -= normal * turned.dot(normal);
+            }
+
+            let turned = turned.normalize_or_zero();
+            let turned = if turned == Vec3::ZERO {
+                heading
+            } else {
+                turned
+            };
+
+            let previous = position;
+            position += turned * step;
+
+            match contact(position) {
+                Some((surface, away)) => {
+                    position = surface;
+                    normal = away;
+                    attached = true;
+                }
+                None => {
+                    normal = frame::transport(normal, heading, turned);
+                    attached = false;
+                }
+            }
+
+            let moved = (position - previous).normalize_or_zero();
+            heading = if moved == Vec3::ZERO { turned } else { moved };
+
+            let distance = points.last().unwrap().distance + position.distance(previous);
+
+            points.push(CurvePoint {
+                position,
+                direction: heading,
+                up: frame::orthogonalize(heading, normal),
+                distance,
+                t: i as f32 / steps as f32,
+            });
+        }
+
+        points
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn tendril(
+        &self,
+        mesh: &mut Mesh,
+        point: &CurvePoint,
+        axis: Vec3,
+        radius: f32,
+        color: Color,
+        obstacle: Option<&TriangleGrid>,
+        ctx: &GenerateContext,
+    ) {
+        let length = self.tendril_length.eval(ctx).max(0.0) * ctx.scale;
+        let coils = self.tendril_coils.eval(ctx).max(0.0);
+
+        if length <= 0.0 || radius <= 0.0 {
+            return;
+        }
+
+        let out = frame::orthogonalize(axis, point.up);
+        let across = axis.cross(out);
+        let coil = length / (1.0 + coils * 2.0);
+
+        let positions = (0..=TENDRIL_SAMPLES).map(|i| {
+            let s = i as f32 / TENDRIL_SAMPLES as f32;
+            let (sin, cos) = (s * coils * TAU).sin_cos();
+
+            let p =
+                point.position + axis * s * length + (out * (1.0 - cos) + across * sin) * coil * s;
+
+            let Some((closest, normal)) =
+                obstacle.and_then(|obstacle| obstacle.closest_point(p, coil + radius))
+            else {
+                return p;
+            };
+
+            let offset = p - closest;
+
+            let away = offset.normalize_or_zero();
+            let away = if away == Vec3::ZERO { normal } else { away };
+
+            closest + away * radius
+        });
+
+        let positions = positions.collect::<Vec<_>>();
+        let mut points = Vec::with_capacity(positions.len());
+
+        for (i, position) in positions.iter().enumerate() {
+            let next = positions.get(i + 1).unwrap_or(position);
+            let previous = positions[i.saturating_sub(1)];
+
+            let heading = (*next - previous).normalize_or_zero();
+            let heading = if heading == Vec3::ZERO { axis } else { heading };
+
+            let distance = points.last().map_or(0.0, |last: &CurvePoint| {
+                last.distance + last.position.distance(*position)
+            });
+
+            points.push(CurvePoint {
+                position: *position,
+                direction: heading,
+                up: frame::orthogonalize(heading, point.up),
+                distance,
+                t: i as f32 / TENDRIL_SAMPLES as f32,
+            });
+        }
+
+        let first = mesh.vertices.len();
+        let radial = ctx.tessellation.radial(radius);
+
+        tube::tube(mesh, &points, radial, |point| {
+            radius * (1.0 - point.t * 0.5)
+        });
+
+        for vertex in mesh.vertices[first..].iter_mut() {
+            vertex.color = color.to_array();
+        }
+    }
+
+    pub fn generate(
+        &self,
+        mesh: &mut Mesh,
+        start: Vec3,
+        direction: Vec3,
+        up: Vec3,
+        ctx: GenerateContext,
+    ) {
+        let up = frame::orthogonalize(direction, up);
+        let radius = self.radius.eval(&ctx).max(0.0) * ctx.scale;
+
+        let support = self.support.mesh(
+            start,
+            direction,
+            up,
+            self.support_width.eval(&ctx).max(MIN_SUPPORT_WIDTH) * ctx.scale,
+            self.support_height.eval(&ctx).max(0.0) * ctx.scale,
+            self.support_radius.eval(&ctx).max(0.001) * ctx.scale,
+            radius,
+            &ctx,
+        );
+
+        if let Some(support) = support.as_ref().filter(|_| self.show_support) {
+            let first = mesh.vertices.len();
+            let offset = first as u32;
+            let color = self.support_color.to_array();
+
+            mesh.vertices.extend(
+                support
+                    .vertices
+                    .iter()
+                    .map(|vertex| Vertex { color, ..*vertex }),
+            );
+            mesh.indices
+                .extend(support.indices.iter().map(|index| index + offset));
+            mesh.seams
+                .extend(support.seams.iter().map(|[a, b]| [a + offset, b + offset]));
+
+            let vertices = first..mesh.vertices.len();
+            mesh.tag(vertices.clone(), Organ::Unknown);
+            ctx.apply_material(mesh, vertices, Material::Bark(self.bark.clone()));
+        }
+
+        let obstacle = support.as_ref().or(ctx.obstacle);
+        let grid = obstacle.map(TriangleGrid::new);
+        let points = self.grow(start, direction, up, grid.as_ref(), &ctx);
+
+        let first = mesh.vertices.len();
+        let radius_at = |point: &CurvePoint| radius * (1.0 - point.t * (1.0 - TIP_RADIUS));
+        let radial = ctx.tessellation.radial(radius);
+
+        tube::tube(mesh, &points, radial, radius_at);
+
+        let rings = mesh.vertices[first..].chunks_mut(radial as usize + 1);
+
+        for (ring, point) in rings.zip(points.iter()) {
+            let color = self.color.at(point.t).to_array();
+
+            for vertex in ring {
+                vertex.color = color;
+            }
+        }
+
+        let spacing = self.leaf_spacing.eval(&ctx).max(0.001) * ctx.scale;
+        let total = points.last().map_or(0.0, |point| point.distance);
+        let count = ((total / spacing) as usize).min(MAX_STEPS);
+
+        let mut nodes = Vec::with_capacity(count);
+        let mut next = spacing;
+
+        for point in points.iter() {
+            if nodes.len() >= count {
+                break;
+            }
+
+            if point.distance >= next {
+                nodes.push(*point);
+                next += spacing;
+            }
+        }
+
+        for (i, point) in nodes.iter().enumerate() {
+            let side = if i % 2 == 0 { 1.0 } else { -1.0 };
+            let axis = (point.direction - point.right() * side * 2.0).normalize();
+
+            let tendril = radius_at(point) * TENDRIL_RADIUS;
+            let color = self.color.at(point.t);
+            self.tendril(mesh, point, axis, tendril, color, grid.as_ref(), &ctx);
+        }
+
+        let vertices = first..mesh.vertices.len();
+
+        mesh.tag(vertices.clone(), Organ::Branch);
+        ctx.apply_material(mesh, vertices, Material::Bark(self.bark.clone()));
+
+        for (i, point) in nodes.iter().enumerate() {
+            let side = if i % 2 == 0 { 1.0 } else { -1.0 };
+
+            let heading =
+                (point.direction + point.right() * side * 2.0 + point.up * 0.5).normalize();
+
+            let ctx = GenerateContext {
+                index: i as u32,
+                t: i as f32 / (nodes.len().max(2) - 1) as f32,
+                parent_radius: radius_at(point),
+                obstacle,
+                ..ctx.child()
+            };
+
+            self.leaf
+                .generate_mesh(mesh, point.position, heading, point.up, ctx);
+        }
+    }
+}
+
+pub enum VineMessage {
+    SetSupport(Support),
+    SetSupportWidth(Gene),
+    SetSupportHeight(Gene),
+    SetSupportRadius(Gene),
+    SetShowSupport(bool),
+    SetLength(Gene),
+    SetRadius(Gene),
+    SetStep(Gene),
+    SetClimb(Gene),
+    SetWrap(Gene),
+    SetWander(Gene),
+    SetLeafSpacing(Gene),
+    SetTendrilLength(Gene),
+    SetTendrilCoils(Gene),
+    SetColor(Gradient),
+    SetSupportColor(Color),
+    SetBark(BarkPattern),
+    ChangeLeaf(Box<PlantMessage>),
+}
+
+impl VineMessage {
+    pub fn handle(self, rng: &mut impl Rng, vine: &mut Vine) {
+        match self {
+            Self::SetSupport(x) => vine.support = x,
+            Self::SetSupportWidth(x) => vine.support_width = x,
+            Self::SetSupportHeight(x) => vine.support_height = x,
+            Self::SetSupportRadius(x) => vine.support_radius = x,
+            Self::SetShowSupport(x) => vine.show_support = x,
+            Self::SetLength(x) => vine.length = x,
+            Self::SetRadius(x) => vine.radius = x,
+            Self::SetStep(x) => vine.step = x,
+            Self::SetClimb(x) => vine.climb = x,
+            Self::SetWrap(x) => vine.wrap = x,
+            Self::SetWander(x) => vine.wander = x,
+            Self::SetLeafSpacing(x) => vine.leaf_spacing = x,
+            Self::SetTendrilLength(x) => vine.tendril_length = x,
+            Self::SetTendrilCoils(x) => vine.tendril_coils = x,
+            Self::SetColor(x) => vine.color = x,
+            Self::SetSupportColor(x) => vine.support_color = x,
+            Self::SetBark(x) => vine.bark = x,
+            Self::ChangeLeaf(msg) => msg.handle(rng, &mut vine.leaf),
+        }
+    }
+}
@@ -3,7 +3,7 @@ mod generate;
 mod mesh;
 mod texture;
 
-use generate::{PlantDna, PlantMessage};
+use generate::{PlantDna, PlantMessage, Tessellation};
 use rand::{prelude::StdRng, Rng, SeedableRng};
 use yew::prelude::*;
 
@@ -22,23 +22,35 @@ pub enum Message {
     ChangeCurrentDna(PlantMessage),
     KeepDna,
     SelectDna(usize),
+    SetTessellation(Tessellation),
+    SetRoots(bool),
 }
 
 pub struct App {
     pub rng: StdRng,
     pub current_dna: PlantDna,
     pub dna_options: [PlantDna; 7],
+    pub tessellation: Tessellation,
+    pub roots: bool,
 }
 
 impl App {
     pub fn plant_mutations<const T: usize>(dna: &PlantDna, rng: &mut impl Rng) -> [PlantDna; T] {
-        let mutations = (0..T).into_iter().map(|_| {
-            let mut dna = dna.clone();
-            dna.mutate(rng, 0.2);
-            dna
-        });
+        let mut mutations = (0..T)
+            .into_iter()
+            .map(|_| {
+                let mut dna = dna.clone();
+                dna.mutate(rng, 0.2);
+                dna
+            })
+            .collect::<Vec<_>>();
+
+        for i in T / 2..T {
+            let other = rng.gen_range(0..T);
+            mutations[i] = mutations[i].crossover(&mutations[other], rng);
+        }
 
-        TryFrom::try_from(mutations.collect::<Vec<_>>()).unwrap()
+        TryFrom::try_from(mutations).unwrap()
     }
 }
 
@@ -57,6 +69,8 @@ impl Component for App {
             current_dna,
             rng,
             dna_options: TryFrom::try_from(dna_options).unwrap(),
+            tessellation: Tessellation::default(),
+            roots: true,
         }
     }
 
@@ -76,12 +90,20 @@ impl Component for App {
                 self.current_dna = self.dna_options[idx].clone();
                 self.dna_options = dna_options;
             }
+            Message::SetTessellation(tessellation) => {
+                self.tessellation = tessellation;
+            }
+            Message::SetRoots(roots) => {
+                self.roots = roots;
+            }
         }
 
         true
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
+        let roots = self.roots;
+
         let plant_options = self.dna_options.iter().enumerate().map(|(i, dna)| {
             html! {
                 <div
@@ -91,6 +113,8 @@ impl Component for App {
                     <PlantViewer
                         rotation=0.0
                         dna={ dna.clone() }
+                        tessellation={ Tessellation::Low }
+                        roots={ self.roots }
                     />
                 </div>
             }
@@ -105,6 +129,17 @@ impl Component for App {
                 </div>
 
                 <div class="dna-options">
+                    <div class="container">
+                        { self.tessellation.view(&ctx.link().callback(Message::SetTessellation)) }
+                        <div class="property">
+                            { "Show Roots" }
+                            <input
+                                type="checkbox"
+                                checked={ self.roots }
+                                onclick={ ctx.link().callback(move |_| Message::SetRoots(!roots)) }
+                            />
+                        </div>
+                    </div>
                     <DnaOptions
                         dna={ self.current_dna.clone() }
                         change_dna={ ctx.link().callback(Message::ChangeCurrentDna) }
@@ -120,6 +155,9 @@ impl Component for App {
                             <PlantViewer
                                 rotation=0.0
                                 dna={ self.current_dna.clone() }
+                                tessellation={ self.tessellation }
+                                roots={ self.roots }
+                                show_stats=true
                             />
                         </div>
                         { for plant_options }
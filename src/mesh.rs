@@ -1,16 +1,78 @@
-use std::{ops::Deref, sync::Arc};
+use std::{
+    collections::HashMap,
+    ops::{Deref, Range},
+    sync::Arc,
+};
 
 use bytemuck::{Pod, Zeroable};
 use glam::Vec3;
 use serde::{Deserialize, Serialize};
 use wgpu::util::DeviceExt;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Organ {
+    Unknown,
+    Branch,
+    Leaf,
+    Flower,
+    Fruit,
+    Root,
+}
+
+impl Organ {
+    pub const ALL: [Self; 6] = [
+        Self::Unknown,
+        Self::Branch,
+        Self::Leaf,
+        Self::Flower,
+        Self::Fruit,
+        Self::Root,
+    ];
+
+    pub fn id(&self) -> u32 {
+        *self as u32
+    }
+
+    pub fn from_id(id: u32) -> Self {
+        Self::ALL.get(id as usize).copied().unwrap_or(Self::Unknown)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Unknown => "Unknown",
+            Self::Branch => "Branch",
+            Self::Leaf => "Leaf",
+            Self::Flower => "Flower",
+            Self::Fruit => "Fruit",
+            Self::Root => "Root",
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Pod, Zeroable)]
 pub struct Vertex {
     pub position: [f32; 3],
     pub normal: [f32; 3],
     pub uv: [f32; 2],
+    pub region: [f32; 4],
+    pub color: [f32; 3],
+    pub organ: u32,
+    pub node: u32,
+}
+
+impl Default for Vertex {
+    fn default() -> Self {
+        Self {
+            position: [0.0; 3],
+            normal: [0.0; 3],
+            uv: [0.0; 2],
+            region: [0.0, 0.0, 1.0, 1.0],
+            color: [1.0; 3],
+            organ: Organ::Unknown.id(),
+            node: 0,
+        }
+    }
 }
 
 impl Vertex {
@@ -37,12 +99,46 @@ impl Vertex {
     pub fn set_uv(&mut self, uv: [f32; 2]) {
         self.uv = uv;
     }
+
+    pub fn get_region(&self) -> [f32; 4] {
+        self.region
+    }
+
+    pub fn set_region(&mut self, region: [f32; 4]) {
+        self.region = region;
+    }
+
+    pub fn get_color(&self) -> [f32; 3] {
+        self.color
+    }
+
+    pub fn set_color(&mut self, color: [f32; 3]) {
+        self.color = color;
+    }
+
+    pub fn get_organ(&self) -> Organ {
+        Organ::from_id(self.organ)
+    }
+
+    pub fn set_organ(&mut self, organ: Organ) {
+        self.organ = organ.id();
+    }
+
+    pub fn get_node(&self) -> u32 {
+        self.node
+    }
+
+    pub fn set_node(&mut self, node: u32) {
+        self.node = node;
+    }
 }
 
 #[derive(Clone, Debug, Default, PartialEq)]
 pub struct Mesh {
     pub vertices: Vec<Vertex>,
     pub indices: Vec<u32>,
+    pub seams: Vec<[u32; 2]>,
+    pub nodes: u32,
 }
 
 impl Mesh {
@@ -70,6 +166,108 @@ impl Mesh {
             .unwrap_or(0.5)
     }
 
+    pub fn tag(&mut self, vertices: Range<usize>, organ: Organ) -> u32 {
+        self.nodes += 1;
+        let node = self.nodes;
+
+        for vertex in self.vertices[vertices].iter_mut() {
+            vertex.organ = organ.id();
+            vertex.node = node;
+        }
+
+        node
+    }
+
+    pub fn filter(&self, mut filter: impl FnMut(&Vertex) -> bool) -> Mesh {
+        let mut mesh = Mesh::default();
+        let mut remap = HashMap::new();
+
+        for triangle in self.indices.chunks_exact(3) {
+            if !filter(&self.vertices[triangle[0] as usize]) {
+                continue;
+            }
+
+            for &index in triangle {
+                let index = *remap.entry(index).or_insert_with(|| {
+                    mesh.vertices.push(self.vertices[index as usize]);
+                    mesh.vertices.len() as u32 - 1
+                });
+
+                mesh.indices.push(index);
+            }
+        }
+
+        mesh
+    }
+
+    pub fn split_organs(&self) -> Vec<(Organ, Mesh)> {
+        Organ::ALL
+            .into_iter()
+            .map(|organ| (organ, self.filter(|vertex| vertex.organ == organ.id())))
+            .filter(|(_, mesh)| !mesh.indices.is_empty())
+            .collect()
+    }
+
+    pub fn area(&self) -> f32 {
+        self.indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let [p0, p1, p2] =
+                    [0, 1, 2].map(|i| Vec3::from(self.vertices[triangle[i] as usize].position));
+
+                (p1 - p0).cross(p2 - p0).length() / 2.0
+            })
+            .sum()
+    }
+
+    fn closest_on_triangle(&self, triangle: usize, point: Vec3) -> (Vec3, Vec3) {
+        let [p0, p1, p2] = [0, 1, 2]
+            .map(|i| Vec3::from(self.vertices[self.indices[triangle * 3 + i] as usize].position));
+
+        let closest = closest_on_triangle(point, p0, p1, p2);
+        let normal = (p1 - p0).cross(p2 - p0).normalize_or_zero();
+
+        let normal = if (point - closest).dot(normal) < 0.0 {
+            -normal
+        } else {
+            normal
+        };
+
+        (closest, normal)
+    }
+
+    pub fn stats(&self) -> MeshStats {
+        let areas = self
+            .split_organs()
+            .into_iter()
+            .map(|(organ, mesh)| (organ, mesh.area()))
+            .collect::<HashMap<_, _>>();
+        let area = |organ: Organ| areas.get(&organ).copied().unwrap_or(0.0);
+
+        let mut nodes = self
+            .vertices
+            .iter()
+            .map(|vertex| vertex.node)
+            .collect::<Vec<_>>();
+        nodes.sort_unstable();
+        nodes.dedup();
+
+        MeshStats {
+            vertices: self.vertices.len(),
+            triangles: self.indices.len() / 3,
+            nodes: nodes.into_iter().filter(|&node| node != 0).count(),
+            leaf_area: area(Organ::Leaf),
+            bark_area: area(Organ::Branch),
+        }
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.vertices
+            .iter()
+            .map(|vert| vert.position[1])
+            .fold(0.0, f32::min)
+    }
+
     pub fn buffers(&self, device: &wgpu::Device) -> (wgpu::Buffer, wgpu::Buffer) {
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("basil-vertex-buffer"),
@@ -119,6 +317,18 @@ impl Mesh {
             self.vertices[i2].normal = From::from(n2 + normal);
         }
 
+        for &[a, b] in self.seams.iter() {
+            let (a, b) = (a as usize, b as usize);
+
+            if a >= self.vertices.len() || b >= self.vertices.len() {
+                continue;
+            }
+
+            let normal = Vec3::from(self.vertices[a].normal) + Vec3::from(self.vertices[b].normal);
+            self.vertices[a].normal = normal.into();
+            self.vertices[b].normal = normal.into();
+        }
+
         for vertex in self.vertices.iter_mut() {
             let normalized = Vec3::from(vertex.normal).normalize_or_zero();
             vertex.normal = normalized.into();
@@ -126,6 +336,140 @@ impl Mesh {
     }
 }
 
+const GRID_RESOLUTION: f32 = 32.0;
+
+pub struct TriangleGrid<'a> {
+    mesh: &'a Mesh,
+    size: f32,
+    bounds: [[i32; 3]; 2],
+    cells: HashMap<[i32; 3], Vec<usize>>,
+}
+
+impl<'a> TriangleGrid<'a> {
+    pub fn new(mesh: &'a Mesh) -> Self {
+        let (min, max) = mesh.vertices.iter().fold(
+            (Vec3::splat(f32::INFINITY), Vec3::splat(f32::NEG_INFINITY)),
+            |(min, max), vertex| {
+                let p = Vec3::from(vertex.position);
+                (min.min(p), max.max(p))
+            },
+        );
+
+        let size = ((max - min).max_element() / GRID_RESOLUTION).max(f32::EPSILON);
+
+        let mut grid = Self {
+            mesh,
+            size,
+            bounds: [[0; 3], [-1; 3]],
+            cells: HashMap::new(),
+        };
+
+        if mesh.indices.is_empty() {
+            return grid;
+        }
+
+        grid.bounds = [grid.cell(min), grid.cell(max)];
+
+        for (i, triangle) in mesh.indices.chunks_exact(3).enumerate() {
+            let [p0, p1, p2] =
+                [0, 1, 2].map(|j| Vec3::from(mesh.vertices[triangle[j] as usize].position));
+
+            let [lo, hi] = [grid.cell(p0.min(p1).min(p2)), grid.cell(p0.max(p1).max(p2))];
+
+            for x in lo[0]..=hi[0] {
+                for y in lo[1]..=hi[1] {
+                    for z in lo[2]..=hi[2] {
+                        grid.cells.entry([x, y, z]).or_default().push(i);
+                    }
+                }
+            }
+        }
+
+        grid
+    }
+
+    fn cell(&self, position: Vec3) -> [i32; 3] {
+        let cell = (position / self.size).floor();
+        [cell.x as i32, cell.y as i32, cell.z as i32]
+    }
+
+    pub fn closest_point(&self, point: Vec3, max_distance: f32) -> Option<(Vec3, Vec3)> {
+        let lo = self.cell(point - Vec3::splat(max_distance));
+        let hi = self.cell(point + Vec3::splat(max_distance));
+        let [min, max] = self.bounds;
+
+        let range = |axis: usize| lo[axis].max(min[axis])..=hi[axis].min(max[axis]);
+
+        range(0)
+            .flat_map(|x| range(1).flat_map(move |y| range(2).map(move |z| [x, y, z])))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .map(|&triangle| self.mesh.closest_on_triangle(triangle, point))
+            .filter(|(closest, _)| closest.distance(point) <= max_distance)
+            .min_by(|a, b| {
+                a.0.distance_squared(point)
+                    .total_cmp(&b.0.distance_squared(point))
+            })
+    }
+}
+
+fn closest_on_triangle(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    let ab = b - a;
+    let ac = c - a;
+
+    let ap = p - a;
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+
+    let bp = p - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = p - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denominator = va + vb + vc;
+    if denominator.abs() <= f32::EPSILON {
+        return a;
+    }
+
+    a + ab * (vb / denominator) + ac * (vc / denominator)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MeshStats {
+    pub vertices: usize,
+    pub triangles: usize,
+    pub nodes: usize,
+    pub leaf_area: f32,
+    pub bark_area: f32,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct SharedMesh {
     pub mesh: Arc<Mesh>,
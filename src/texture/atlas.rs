@@ -0,0 +1,121 @@
+use glam::Vec2;
+
+use super::{Pixel, Texture};
+
+const PADDING: u32 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasRegion {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl AtlasRegion {
+    pub const FULL: Self = Self {
+        min: Vec2::ZERO,
+        max: Vec2::ONE,
+    };
+
+    pub fn map(&self, uv: Vec2) -> Vec2 {
+        self.min + uv.fract() * (self.max - self.min)
+    }
+
+    pub fn to_array(self) -> [f32; 4] {
+        [self.min.x, self.min.y, self.max.x, self.max.y]
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Atlas {
+    pub texture: Texture,
+    pub regions: Vec<AtlasRegion>,
+}
+
+impl Atlas {
+    pub fn pack(textures: &[Texture]) -> Self {
+        if textures.is_empty() {
+            return Self {
+                texture: Texture::white(),
+                regions: Vec::new(),
+            };
+        }
+
+        let mut order = (0..textures.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| std::cmp::Reverse(textures[i].height));
+
+        let area = textures
+            .iter()
+            .map(|texture| (texture.width + PADDING * 2) * (texture.height + PADDING * 2))
+            .sum::<u32>();
+
+        let widest = textures
+            .iter()
+            .map(|texture| texture.width + PADDING * 2)
+            .max()
+            .unwrap_or(1);
+
+        let width = ((area as f32).sqrt().ceil() as u32)
+            .max(widest)
+            .next_power_of_two();
+
+        let mut offsets = vec![(0, 0); textures.len()];
+        let mut x = 0;
+        let mut y = 0;
+        let mut row_height = 0;
+
+        for &i in order.iter() {
+            let w = textures[i].width + PADDING * 2;
+            let h = textures[i].height + PADDING * 2;
+
+            if x + w > width {
+                x = 0;
+                y += row_height;
+                row_height = 0;
+            }
+
+            offsets[i] = (x + PADDING, y + PADDING);
+
+            x += w;
+            row_height = row_height.max(h);
+        }
+
+        let height = (y + row_height).next_power_of_two();
+
+        let mut atlas = Texture {
+            pixels: vec![Pixel::TRANSPARENT; (width * height) as usize],
+            width,
+            height,
+        };
+
+        let mut regions = Vec::with_capacity(textures.len());
+
+        for (texture, &(ox, oy)) in textures.iter().zip(offsets.iter()) {
+            let padding = PADDING as i32;
+
+            for y in -padding..texture.height as i32 + padding {
+                for x in -padding..texture.width as i32 + padding {
+                    let sx = x.rem_euclid(texture.width as i32) as u32;
+                    let sy = y.rem_euclid(texture.height as i32) as u32;
+
+                    let dx = (ox as i32 + x) as u32;
+                    let dy = (oy as i32 + y) as u32;
+
+                    atlas.pixels[(dy * width + dx) as usize] =
+                        texture.pixels[(sy * texture.width + sx) as usize];
+                }
+            }
+
+            let size = Vec2::new(width as f32, height as f32);
+
+            regions.push(AtlasRegion {
+                min: Vec2::new(ox as f32, oy as f32) / size,
+                max: Vec2::new((ox + texture.width) as f32, (oy + texture.height) as f32) / size,
+            });
+        }
+
+        Self {
+            texture: atlas,
+            regions,
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use glam::Vec2;
+
+use super::{Pixel, Texture};
+
+impl Texture {
+    pub fn decode_png(bytes: &[u8]) -> Result<Self, png::DecodingError> {
+        let mut decoder = png::Decoder::new(bytes);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        let buffer = &buffer[..info.buffer_size()];
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer
+                .chunks_exact(4)
+                .map(|p| Pixel::rgba(p[0], p[1], p[2], p[3]))
+                .collect(),
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .map(|p| Pixel::rgb(p[0], p[1], p[2]))
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .map(|p| Pixel::rgba(p[0], p[0], p[0], p[1]))
+                .collect(),
+            png::ColorType::Grayscale => buffer.iter().map(|&p| Pixel::rgb(p, p, p)).collect(),
+            png::ColorType::Indexed => unreachable!(),
+        };
+
+        Ok(Self {
+            pixels,
+            width: info.width,
+            height: info.height,
+        })
+    }
+
+    pub fn encode_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let mut bytes = Vec::new();
+
+        let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(self.data())?;
+        writer.finish()?;
+
+        Ok(bytes)
+    }
+
+    pub fn sample(&self, uv: Vec2) -> Pixel {
+        let uv = uv.fract();
+        let x = ((uv.x * self.width as f32) as u32).min(self.width - 1);
+        let y = ((uv.y * self.height as f32) as u32).min(self.height - 1);
+
+        self.pixels[(y * self.width + x) as usize]
+    }
+}
+
+pub fn png_url(texture: &Texture) -> Option<String> {
+    let bytes = texture.encode_png().ok()?;
+    let blob = gloo_file::Blob::new_with_options(bytes.as_slice(), Some("image/png"));
+
+    web_sys::Url::create_object_url_with_blob(&blob.into()).ok()
+}
@@ -1,6 +1,11 @@
+pub mod atlas;
+pub mod encoding;
+pub mod procedural;
+
 use std::{ops::Deref, sync::Arc};
 
 use bytemuck::{Pod, Zeroable};
+use glam::Vec2;
 use serde::{Deserialize, Serialize};
 use wgpu::util::DeviceExt;
 
@@ -27,7 +32,7 @@ impl Pixel {
     }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Texture {
     pub pixels: Vec<Pixel>,
     pub width: u32,
@@ -43,6 +48,27 @@ impl Texture {
         }
     }
 
+    pub fn from_fn(width: u32, height: u32, mut f: impl FnMut(Vec2) -> Pixel) -> Self {
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+
+        for y in 0..height {
+            for x in 0..width {
+                let uv = Vec2::new(
+                    (x as f32 + 0.5) / width as f32,
+                    (y as f32 + 0.5) / height as f32,
+                );
+
+                pixels.push(f(uv));
+            }
+        }
+
+        Self {
+            pixels,
+            width,
+            height,
+        }
+    }
+
     pub fn data(&self) -> &[u8] {
         bytemuck::cast_slice(&self.pixels)
     }
@@ -68,7 +94,7 @@ impl Texture {
     }
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct SharedTexture {
     texture: Arc<Texture>,
 }
@@ -0,0 +1,153 @@
+use std::f32::consts::TAU;
+
+use glam::{Vec2, Vec3};
+
+use super::{Pixel, Texture};
+
+fn hash(x: i32, y: i32, seed: u32) -> f32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6b343)
+        ^ (y as u32).wrapping_mul(0xd8163841)
+        ^ seed.wrapping_mul(0xcb1ab31f);
+
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5bd1e995);
+    h ^= h >> 15;
+
+    (h & 0xffffff) as f32 / 0xffffff as f32
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let x = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    x * x * (3.0 - 2.0 * x)
+}
+
+pub fn noise(p: Vec2, period: i32, seed: u32) -> f32 {
+    let i = p.floor();
+    let f = p - i;
+
+    let x0 = (i.x as i32).rem_euclid(period);
+    let y0 = (i.y as i32).rem_euclid(period);
+    let x1 = (x0 + 1).rem_euclid(period);
+    let y1 = (y0 + 1).rem_euclid(period);
+
+    let u = f * f * (Vec2::splat(3.0) - f * 2.0);
+
+    let a = hash(x0, y0, seed);
+    let b = hash(x1, y0, seed);
+    let c = hash(x0, y1, seed);
+    let d = hash(x1, y1, seed);
+
+    let top = a + (b - a) * u.x;
+    let bottom = c + (d - c) * u.x;
+
+    top + (bottom - top) * u.y
+}
+
+pub fn fbm(uv: Vec2, frequency: i32, octaves: u32, seed: u32) -> f32 {
+    let mut value = 0.0;
+    let mut amplitude = 0.5;
+    let mut total = 0.0;
+    let mut frequency = frequency.max(1);
+
+    for octave in 0..octaves {
+        value += noise(uv * frequency as f32, frequency, seed.wrapping_add(octave)) * amplitude;
+        total += amplitude;
+
+        amplitude *= 0.5;
+        frequency *= 2;
+    }
+
+    value / total
+}
+
+fn color(pixel: Pixel) -> Vec3 {
+    Vec3::new(pixel.r as f32, pixel.g as f32, pixel.b as f32) / 255.0
+}
+
+fn pixel(color: Vec3) -> Pixel {
+    let color = color.clamp(Vec3::ZERO, Vec3::ONE) * 255.0;
+    Pixel::rgb(color.x as u8, color.y as u8, color.z as u8)
+}
+
+pub fn bark(
+    size: u32,
+    base: Vec3,
+    photo: Option<&Texture>,
+    crack: Vec3,
+    ridges: f32,
+    roughness: f32,
+    seed: u32,
+) -> Texture {
+    let ridges = ridges.round().max(1.0);
+
+    Texture::from_fn(size, size, |uv| {
+        let warp = fbm(uv, 4, 4, seed) - 0.5;
+        let ridge = ((uv.x + warp * roughness) * ridges * TAU).sin() * 0.5 + 0.5;
+        let ridge = smoothstep(0.1, 0.6, ridge);
+
+        let grain = fbm(uv, 16, 3, seed.wrapping_add(16));
+
+        let base = photo.map_or(base, |photo| color(photo.sample(uv)));
+
+        pixel(crack.lerp(base, ridge) * (0.8 + grain * 0.4))
+    })
+}
+
+pub struct Veins {
+    pub color: Vec3,
+    pub count: f32,
+    pub angle: f32,
+}
+
+pub fn leaf(
+    size: u32,
+    base: Vec3,
+    photo: Option<&Texture>,
+    tip: Vec3,
+    veins: Veins,
+    seed: u32,
+) -> Texture {
+    let Veins {
+        color: vein,
+        count: veins,
+        angle: vein_angle,
+    } = veins;
+
+    Texture::from_fn(size, size, |uv| {
+        let across = (uv.x - 0.5).abs() * 2.0;
+
+        let midrib = 1.0 - smoothstep(0.0, 0.04, across);
+
+        let side = (uv.y - across * vein_angle) * veins.max(1.0);
+        let side = 1.0 - smoothstep(0.0, 0.08, (side - side.round()).abs());
+        let side = side * (1.0 - across);
+
+        let vein_amount = midrib.max(side * 0.6);
+
+        let mottle = fbm(uv, 8, 3, seed);
+        let base = photo.map_or(base, |photo| color(photo.sample(uv)));
+        let tint = base.lerp(tip, uv.y) * (0.85 + mottle * 0.3);
+
+        pixel(tint.lerp(vein, vein_amount))
+    })
+}
+
+pub fn petal(size: u32, center: Vec3, edge: Vec3, streaks: f32, seed: u32) -> Texture {
+    Texture::from_fn(size, size, |uv| {
+        let gradient = smoothstep(0.0, 0.7, uv.y);
+        let streak = fbm(Vec2::new(uv.x, uv.y * 0.1), 16, 2, seed);
+        let streak = 1.0 - streak * streaks.clamp(0.0, 1.0);
+
+        pixel(center.lerp(edge, gradient) * (0.7 + streak * 0.3))
+    })
+}
+
+pub fn skin(size: u32, base: Vec3, speckle: Vec3, speckles: f32, seed: u32) -> Texture {
+    Texture::from_fn(size, size, |uv| {
+        let shade = fbm(uv, 4, 3, seed);
+        let spots = fbm(uv, 32, 2, seed.wrapping_add(32));
+        let spots = smoothstep(1.0 - speckles.clamp(0.0, 1.0) * 0.5, 1.0, spots);
+
+        pixel(base.lerp(speckle, spots) * (0.9 + shade * 0.2))
+    })
+}